@@ -0,0 +1,689 @@
+//! Chord identification — naming the chord a set of strings sounds.
+//!
+//! Going from a chord name to positions is handled by `guitar`; this module
+//! goes the other way. The strings picked at a fret, with a combination of
+//! pedals and levers engaged, sound a set of pitches; every chord type whose
+//! notes are exactly those pitch classes is reported with its root and the
+//! inversion given by the lowest string.
+//!
+//! The grip finder searches the neck for playable voicings of a chord: two to
+//! four neighbouring strings (one string may be skipped) under the bar at one
+//! fret, where every string sounds a chord tone. It can also slant the bar, so
+//! the strings are stopped at different frets, as lap steel players do to reach
+//! chords a straight bar cannot.
+
+use crate::{
+    copedent::{Copedent, CopedentError, pedal_and_levers},
+    guitar::{Bar, FretRange, Guitar, NeckPositions, notes_on_neck, populate_neck_pitches},
+    spelling::{chord_notes, parse_note_name, spellings},
+};
+use rust_music_theory::{
+    chord::{Chord, Number, Quality},
+    note::Pitch,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    ops::RangeInclusive,
+};
+
+/// Fewest and most strings picked in a grip
+const GRIP_STRINGS: RangeInclusive<usize> = 2..=4;
+
+/// Every chord type the tool knows, named the way `Chord::from_regex` reads
+/// them. `list chords` prints these names and `identify` recognises exactly
+/// these chords.
+///
+/// The names are taken from the rust-music-theory crate:
+/// <https://github.com/ozankasikci/rust-music-theory/blob/src/bin/rustmt.rs>
+pub const CHORD_TYPES: [(Quality, Number, &str); 22] = [
+    (Quality::Major, Number::Triad, "Major Triad"),
+    (Quality::Minor, Number::Triad, "Minor Triad"),
+    (Quality::Suspended2, Number::Triad, "Suspended2 Triad"),
+    (Quality::Suspended4, Number::Triad, "Suspended4 Triad"),
+    (Quality::Augmented, Number::Triad, "Augmented Triad"),
+    (Quality::Diminished, Number::Triad, "Diminished Triad"),
+    (Quality::Major, Number::Seventh, "Major Seventh"),
+    (Quality::Minor, Number::Seventh, "Minor Seventh"),
+    (Quality::Augmented, Number::Seventh, "Augmented Seventh"),
+    (
+        Quality::Augmented,
+        Number::MajorSeventh,
+        "Augmented Major Seventh",
+    ),
+    (Quality::Diminished, Number::Seventh, "Diminished Seventh"),
+    (
+        Quality::HalfDiminished,
+        Number::Seventh,
+        "Half Diminished Seventh",
+    ),
+    (Quality::Minor, Number::MajorSeventh, "Minor Major Seventh"),
+    (Quality::Dominant, Number::Seventh, "Dominant Seventh"),
+    (Quality::Dominant, Number::Ninth, "Dominant Ninth"),
+    (Quality::Major, Number::Ninth, "Major Ninth"),
+    (Quality::Dominant, Number::Eleventh, "Dominant Eleventh"),
+    (Quality::Major, Number::Eleventh, "Major Eleventh"),
+    (Quality::Minor, Number::Eleventh, "Minor Eleventh"),
+    (Quality::Dominant, Number::Thirteenth, "Dominant Thirteenth"),
+    (Quality::Major, Number::Thirteenth, "Major Thirteenth"),
+    (Quality::Minor, Number::Thirteenth, "Minor Thirteenth"),
+];
+
+/// Parse a chord such as "A Major Triad" or "F# minor seventh". The names in
+/// [`CHORD_TYPES`] are matched first, ignoring case, so every chord `identify`
+/// can name is read as that chord; other names go to `Chord::from_regex`.
+pub fn parse_chord(name: &str) -> Option<Chord> {
+    let name = name.trim();
+    let known = name
+        .split_once(char::is_whitespace)
+        .and_then(|(root, kind)| {
+            let root = parse_note_name(root)?;
+            let (quality, number, _) = CHORD_TYPES
+                .iter()
+                .find(|(_, _, name)| name.eq_ignore_ascii_case(kind.trim()))?;
+            Some(Chord::new(root, *quality, *number))
+        });
+    known.or_else(|| Chord::from_regex(name).ok())
+}
+
+/// Errors that can occur while identifying a chord
+#[derive(Debug)]
+pub enum IdentifyError {
+    /// The pedals and levers cannot be played together
+    Copedent(CopedentError),
+    /// No strings were picked
+    NoStrings,
+    /// A string number is not on the guitar (strings count from 1)
+    UnknownString(usize),
+    /// The fret is past the end of the fretboard
+    FretOutOfRange(usize),
+    /// The slanted bar stops a string between two frets, or below the nut
+    BetweenFrets(Bar),
+    /// A string would sound outside the MIDI range, e.g. a low string lowered
+    /// further by a lever
+    PitchOutOfRange(usize),
+}
+
+impl fmt::Display for IdentifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Copedent(e) => write!(f, "{e}"),
+            Self::NoStrings => write!(f, "no strings given"),
+            Self::UnknownString(string) => write!(f, "string {string} is not on the guitar"),
+            Self::FretOutOfRange(fret) => write!(f, "fret {fret} is not on the fretboard"),
+            Self::BetweenFrets(bar) => {
+                write!(f, "a bar at {bar} does not stop every string on a fret")
+            }
+            Self::PitchOutOfRange(string) => {
+                write!(f, "string {string} would sound outside the MIDI range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdentifyError {}
+
+impl From<CopedentError> for IdentifyError {
+    fn from(e: CopedentError) -> Self {
+        Self::Copedent(e)
+    }
+}
+
+/// A chord that matches the notes of a grip
+#[derive(Debug, Clone)]
+pub struct ChordMatch {
+    /// The chord, with its inversion set from the lowest note
+    pub chord: Chord,
+    /// The chord's name, e.g. "A Major Triad"
+    pub name: String,
+}
+
+impl ChordMatch {
+    /// The lowest note of the chord as played
+    pub fn bass(&self) -> Pitch {
+        // An inverted chord lists its notes from the bass up
+        chord_notes(&self.chord)
+            .first()
+            .map_or(self.chord.root, |note| note.pitch)
+    }
+}
+
+impl fmt::Display for ChordMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const INVERSIONS: [&str; 6] = ["first", "second", "third", "fourth", "fifth", "sixth"];
+        match usize::from(self.chord.inversion).checked_sub(1) {
+            None => write!(f, "{}, root position", self.name),
+            Some(i) => write!(
+                f,
+                "{}, {} inversion ({} in the bass)",
+                self.name,
+                INVERSIONS.get(i).unwrap_or(&"upper"),
+                self.bass()
+            ),
+        }
+    }
+}
+
+/// The name of a chord as `Chord::from_regex` reads it, e.g. "Bb Dominant Seventh"
+pub fn chord_name(chord: &Chord) -> String {
+    CHORD_TYPES
+        .iter()
+        .find(|(quality, number, _)| *quality == chord.quality && *number == chord.number)
+        .map_or_else(
+            || format!("{} {} {}", chord.root, chord.quality, chord.number),
+            |(_, _, name)| format!("{} {name}", chord.root),
+        )
+}
+
+/// The MIDI notes the given strings sound under the bar with a combination of
+/// pedals and levers engaged, in the order the strings are given. Strings
+/// count from 1, as on the copedent chart.
+pub fn sounding_pitches(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    bar: Bar,
+    strings: &[usize],
+) -> Result<Vec<u8>, IdentifyError> {
+    if strings.is_empty() {
+        return Err(IdentifyError::NoStrings);
+    }
+    let frets = bar.frets(strings).ok_or(IdentifyError::BetweenFrets(bar))?;
+    if let Some(&fret) = frets.iter().find(|&&fret| fret > guitar.frets) {
+        return Err(IdentifyError::FretOutOfRange(fret));
+    }
+    let changes = pedal_and_levers(copedent, position, guitar.tuning.len())?.copedent_change;
+    let neck = populate_neck_pitches(guitar, &changes);
+    strings
+        .iter()
+        .zip(frets)
+        .map(|(&string, fret)| {
+            string
+                .checked_sub(1)
+                .and_then(|i| neck.get(i))
+                .ok_or(IdentifyError::UnknownString(string))?[fret]
+                .ok_or(IdentifyError::PitchOutOfRange(string))
+        })
+        .collect()
+}
+
+/// Name every chord whose notes are exactly the pitch classes of `midi`. The
+/// lowest note sets the inversion.
+pub fn chords_from_pitches(midi: &[u8]) -> Vec<ChordMatch> {
+    let pitch_classes: BTreeSet<u8> = midi.iter().map(|note| note % 12).collect();
+    let Some(bass) = midi.iter().min().map(|note| note % 12) else {
+        return Vec::new();
+    };
+
+    let mut matches = Vec::new();
+    for &root in &pitch_classes {
+        for (quality, number, name) in CHORD_TYPES {
+            let chord = Chord::new(Pitch::from_u8(root), quality, number);
+            let chord_classes: Vec<u8> = chord_notes(&chord)
+                .iter()
+                .map(|note| note.pitch.into_u8())
+                .collect();
+            if chord_classes.iter().copied().collect::<BTreeSet<_>>() != pitch_classes {
+                continue;
+            }
+
+            let root = spell_root(root, quality, number);
+            let inversion = chord_classes
+                .iter()
+                .position(|&pitch_class| pitch_class == bass)
+                .unwrap_or(0);
+            matches.push(ChordMatch {
+                chord: Chord::with_inversion(
+                    root,
+                    quality,
+                    number,
+                    u8::try_from(inversion).unwrap_or(0),
+                ),
+                name: format!("{root} {name}"),
+            });
+        }
+    }
+
+    // Root position chords first
+    matches.sort_by_key(|m| m.chord.inversion);
+    matches
+}
+
+/// Spell the root of a chord with whichever name gives the chord the fewest
+/// sharps and flats, so a chord on pitch class 10 is Bb rather than A#. On a
+/// tie the root with fewer accidentals wins: F diminished rather than E#.
+fn spell_root(pitch_class: u8, quality: Quality, number: Number) -> Pitch {
+    let accidentals = |root: &Pitch| {
+        let chord: u32 = chord_notes(&Chord::new(*root, quality, number))
+            .iter()
+            .map(|note| u32::from(note.pitch.accidental.unsigned_abs()))
+            .sum();
+        (chord, root.accidental.unsigned_abs())
+    };
+    spellings(pitch_class)
+        .into_iter()
+        .min_by_key(accidentals)
+        .unwrap_or_else(|| Pitch::from_u8(pitch_class))
+}
+
+/// The chords a set of strings makes under the bar
+#[derive(Debug, Clone)]
+pub struct Identified {
+    /// The MIDI notes sounded, in the order the strings were given
+    pub midi: Vec<u8>,
+    /// Every chord those notes make, root position chords first
+    pub chords: Vec<ChordMatch>,
+}
+
+/// Name every chord the given strings make under the bar with a combination of
+/// pedals and levers engaged, with its root and inversion. Strings count
+/// from 1. A chord matches when its notes and the notes sounded are the same
+/// pitch classes, so a diminished seventh is named from each of its notes.
+pub fn identify_chords(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    bar: Bar,
+    strings: &[usize],
+) -> Result<Identified, IdentifyError> {
+    let midi = sounding_pitches(guitar, copedent, position, bar, strings)?;
+    let chords = chords_from_pitches(&midi);
+    Ok(Identified { midi, chords })
+}
+
+/// A voicing of a chord: strings picked under the bar, each sounding a chord
+/// tone
+#[derive(Debug, Clone)]
+pub struct Grip {
+    /// The notes picked, highest string first
+    pub notes: Vec<NeckPositions>,
+    /// Where the bar lies
+    pub bar: Bar,
+    /// Chord tones the grip leaves out, spelled as in the chord
+    pub missing: Vec<Pitch>,
+    /// Whether the lowest note is the root of the chord
+    pub root_in_bass: bool,
+    /// The change the pedals and levers make to each string, string 1 first
+    pub changes: Vec<i8>,
+}
+
+impl Grip {
+    /// The fret the bar is at on the highest string
+    pub fn fret(&self) -> usize {
+        self.bar.fret
+    }
+
+    /// The strings picked, counting from 1
+    pub fn strings(&self) -> Vec<usize> {
+        self.notes.iter().map(|note| note.string + 1).collect()
+    }
+
+    /// Whether every chord tone is in the grip
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Sets of strings that can be picked together: two to four neighbouring
+/// strings, or the same with one string in between skipped. Strings count
+/// from 0.
+fn string_sets(string_count: usize) -> Vec<Vec<usize>> {
+    let mut sets = Vec::new();
+    for first in 0..string_count {
+        for picked in GRIP_STRINGS {
+            // Neighbouring strings
+            if first + picked <= string_count {
+                sets.push((first..first + picked).collect());
+            }
+            // One string skipped, never the outside ones
+            if first + picked < string_count {
+                for skipped in 1..picked {
+                    sets.push(
+                        (first..=first + picked)
+                            .filter(|&string| string != first + skipped)
+                            .collect(),
+                    );
+                }
+            }
+        }
+    }
+    sets
+}
+
+/// Find the grips for a chord within a range of frets with a combination of
+/// pedals and levers engaged. Grips sound at least two different chord tones.
+/// With a `max_slant` above 0 the bar may also be slanted across up to that
+/// many frets, as long as every string picked is stopped on a fret.
+///
+/// Complete grips come first, then those with the root in the bass, then
+/// straight bars before slants and lower frets.
+pub fn find_grips(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    chord: &Chord,
+    frets: FretRange,
+    max_slant: usize,
+) -> Result<Vec<Grip>, CopedentError> {
+    let chord_tones = chord_notes(chord);
+    let changes = pedal_and_levers(copedent, position, guitar.tuning.len())?.copedent_change;
+    let neck: HashMap<(usize, usize), NeckPositions> =
+        notes_on_neck(guitar, &changes, &chord_tones)
+            .into_iter()
+            .map(|note| ((note.string, note.fret), note))
+            .collect();
+    let sets = string_sets(guitar.tuning.len());
+    let max_slant = isize::try_from(max_slant).unwrap_or(isize::MAX);
+    let on_neck = |fret: &usize| frets.contains(*fret) && *fret <= guitar.frets;
+
+    let mut grips = Vec::new();
+    for fret in frets.frets().filter(on_neck) {
+        for slant in -max_slant..=max_slant {
+            let bar = Bar { fret, slant };
+            for strings in &sets {
+                let Some(notes) = bar
+                    .frets(strings)
+                    .filter(|frets| frets.iter().all(on_neck))
+                    .and_then(|frets| {
+                        strings
+                            .iter()
+                            .zip(frets)
+                            .map(|(&string, fret)| neck.get(&(string, fret)).cloned())
+                            .collect::<Option<Vec<_>>>()
+                    })
+                else {
+                    continue;
+                };
+
+                let sounded: BTreeSet<u8> = notes.iter().map(|note| note.pitch.into_u8()).collect();
+                if sounded.len() < 2 {
+                    continue;
+                }
+                let missing = chord_tones
+                    .iter()
+                    .map(|note| note.pitch)
+                    .filter(|pitch| !sounded.contains(&pitch.into_u8()))
+                    .collect();
+                let root_in_bass = notes
+                    .iter()
+                    .min_by_key(|note| note.midi)
+                    .is_some_and(|bass| bass.pitch.into_u8() == chord.root.into_u8());
+                grips.push(Grip {
+                    notes,
+                    bar,
+                    missing,
+                    root_in_bass,
+                    changes: changes.clone(),
+                });
+            }
+        }
+    }
+
+    grips.sort_by_key(|grip| {
+        (
+            grip.missing.len(),
+            !grip.root_in_bass,
+            grip.bar.slant.unsigned_abs(),
+            grip.fret(),
+        )
+    });
+    Ok(grips)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        guitar::Slant,
+        test_support::{e9, names},
+    };
+
+    #[test]
+    fn test_identify_open_strings() {
+        let guitar = e9();
+        let matches = identify_chords(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            Bar::straight(0),
+            &[5, 6, 8],
+        )
+        .unwrap()
+        .chords;
+        assert_eq!(names(&matches), ["E Major Triad, root position"]);
+        let matches = identify_chords(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            Bar::straight(0),
+            &[3, 4, 5],
+        )
+        .unwrap()
+        .chords;
+        assert_eq!(
+            names(&matches),
+            ["E Major Triad, second inversion (B in the bass)"]
+        );
+
+        // At the eighth fret strings 4, 5 and 6 give C, G and E
+        let matches = identify_chords(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            Bar::straight(8),
+            &[4, 5, 6],
+        )
+        .unwrap()
+        .chords;
+        assert_eq!(
+            names(&matches),
+            ["C Major Triad, first inversion (E in the bass)"]
+        );
+    }
+
+    #[test]
+    fn test_identify_with_pedals() {
+        let guitar = e9();
+        let position = vec!["A".to_string(), "B".to_string()];
+        // A and B down turn E major into A major: A, E and C# on strings 3, 4 and 5
+        let matches = identify_chords(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            Bar::straight(0),
+            &[3, 4, 5],
+        )
+        .unwrap()
+        .chords;
+        assert_eq!(
+            names(&matches),
+            ["A Major Triad, first inversion (C# in the bass)"]
+        );
+    }
+
+    #[test]
+    fn test_identify_every_name() {
+        let matches = chords_from_pitches(&[57, 60, 64, 67]);
+        assert_eq!(names(&matches), ["A Minor Seventh, root position"]);
+
+        let matches = chords_from_pitches(&[60, 64, 67, 69]);
+        assert_eq!(
+            names(&matches),
+            ["A Minor Seventh, first inversion (C in the bass)"]
+        );
+
+        // A diminished seventh can be named from any of its notes
+        assert_eq!(chords_from_pitches(&[59, 62, 65, 68]).len(), 4);
+        assert!(chords_from_pitches(&[60, 61]).is_empty());
+    }
+
+    #[test]
+    fn test_chord_types_parse_as_named() {
+        // `chord` takes these names, so each must read back as its own type; the
+        // regex alone reads a minor major seventh as a minor seventh
+        for (quality, number, name) in CHORD_TYPES {
+            let chord = parse_chord(&format!("C {name}")).unwrap();
+            assert_eq!((chord.quality, chord.number), (quality, number), "{name}");
+            let chord = parse_chord(&format!("c {}", name.to_lowercase())).unwrap();
+            assert_eq!((chord.quality, chord.number), (quality, number), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_identify_spells_the_root() {
+        let matches = chords_from_pitches(&[58, 62, 65]);
+        assert_eq!(names(&matches), ["Bb Major Triad, root position"]);
+        assert_eq!(matches[0].bass().to_string(), "Bb");
+        let matches = chords_from_pitches(&[65, 68, 71]);
+        assert_eq!(names(&matches), ["F Diminished Triad, root position"]);
+    }
+
+    #[test]
+    fn test_identify_rejects_bad_input() {
+        let guitar = e9();
+        let copedent = Copedent::default();
+        assert!(matches!(
+            identify_chords(&guitar, &copedent, &[], Bar::straight(0), &[]),
+            Err(IdentifyError::NoStrings)
+        ));
+        assert!(matches!(
+            identify_chords(&guitar, &copedent, &[], Bar::straight(0), &[0]),
+            Err(IdentifyError::UnknownString(0))
+        ));
+        assert!(matches!(
+            identify_chords(&guitar, &copedent, &[], Bar::straight(0), &[11]),
+            Err(IdentifyError::UnknownString(11))
+        ));
+        assert!(matches!(
+            identify_chords(&guitar, &copedent, &[], Bar::straight(25), &[1]),
+            Err(IdentifyError::FretOutOfRange(25))
+        ));
+        let position = vec!["LKL".to_string(), "LKR".to_string()];
+        assert!(matches!(
+            identify_chords(&guitar, &copedent, &position, Bar::straight(0), &[1]),
+            Err(IdentifyError::Copedent(_))
+        ));
+        // LKR lowers string 4 below MIDI 0
+        let low = Guitar::new("Low", "66, 63, 68, 0").unwrap();
+        let position = vec!["LKR".to_string()];
+        assert!(matches!(
+            identify_chords(&low, &copedent, &position, Bar::straight(0), &[3, 4]),
+            Err(IdentifyError::PitchOutOfRange(4))
+        ));
+    }
+
+    #[test]
+    fn test_string_sets() {
+        let sets = string_sets(4);
+        assert!(sets.contains(&vec![0, 1]));
+        assert!(sets.contains(&vec![0, 2]));
+        assert!(sets.contains(&vec![0, 1, 2, 3]));
+        assert!(sets.contains(&vec![0, 1, 3]));
+        assert!(sets.contains(&vec![0, 2, 3]));
+        assert!(!sets.contains(&vec![0, 3]), "two strings skipped");
+        assert!(!sets.contains(&vec![0, 1, 2, 3, 4]), "off the guitar");
+        assert_eq!(sets.len(), 10);
+    }
+
+    #[test]
+    fn test_find_grips() {
+        let guitar = e9();
+        let chord = Chord::from_regex("E major").unwrap();
+        let grips = find_grips(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            &chord,
+            FretRange::first_twelve(),
+            0,
+        )
+        .unwrap();
+
+        // Every grip is a chord voicing at one fret
+        for grip in &grips {
+            assert!(grip.notes.iter().all(|note| note.fret == grip.fret()));
+            assert!((2..=4).contains(&grip.notes.len()));
+        }
+
+        // The best grip is complete with the root in the bass: the open E on string 8
+        // under strings 4 to 6, skipping the F# of string 7
+        let best = &grips[0];
+        assert!(best.is_complete());
+        assert!(best.root_in_bass);
+        assert_eq!(best.fret(), 0);
+        assert_eq!(best.strings(), [4, 5, 6, 8]);
+
+        // Complete grips come before incomplete ones
+        let first_incomplete = grips.iter().position(|g| !g.is_complete()).unwrap();
+        assert!(grips[first_incomplete..].iter().all(|g| !g.is_complete()));
+        assert_eq!(
+            grips[first_incomplete].missing.len(),
+            1,
+            "two-string grips leave one note out"
+        );
+    }
+
+    #[test]
+    fn test_find_grips_with_pedals() {
+        let guitar = e9();
+        let chord = Chord::from_regex("A major").unwrap();
+        let position = vec!["A".to_string(), "B".to_string()];
+        let grips = find_grips(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            &chord,
+            FretRange { start: 0, end: 0 },
+            0,
+        )
+        .unwrap();
+
+        // Strings 3 to 6 give A, E, C#, A with the pedals down
+        assert!(
+            grips
+                .iter()
+                .any(|g| g.strings() == [3, 4, 5, 6] && g.root_in_bass)
+        );
+        assert!(grips.iter().all(|g| g.fret() == 0));
+    }
+
+    #[test]
+    fn test_identify_under_a_slant() {
+        let guitar = e9();
+        let copedent = Copedent::default();
+        // Strings 4 and 6 at frets 5 and 4 with a forward slant: A and C
+        let bar = Bar { fret: 5, slant: -1 };
+        assert_eq!(
+            sounding_pitches(&guitar, &copedent, &[], bar, &[4, 6]).unwrap(),
+            [69, 60]
+        );
+        assert!(matches!(
+            identify_chords(&guitar, &copedent, &[], bar, &[4, 5, 6]),
+            Err(IdentifyError::BetweenFrets(_))
+        ));
+
+        // A reverse slant from fret 2 on string 3 to fret 4 on string 5: Bb, G and Eb
+        let bar = Bar { fret: 2, slant: 2 };
+        let identified = identify_chords(&guitar, &copedent, &[], bar, &[3, 4, 5]).unwrap();
+        assert_eq!(identified.midi, [70, 67, 63]);
+        assert_eq!(names(&identified.chords), ["Eb Major Triad, root position"]);
+    }
+
+    #[test]
+    fn test_find_grips_with_slants() {
+        let guitar = e9();
+        let chord = Chord::from_regex("A minor").unwrap();
+        let frets = FretRange::first_twelve();
+        let copedent = Copedent::default();
+        let straight = find_grips(&guitar, &copedent, &[], &chord, frets, 0).unwrap();
+        let slanted = find_grips(&guitar, &copedent, &[], &chord, frets, 2).unwrap();
+
+        assert!(straight.iter().all(|g| g.bar.kind() == Slant::Straight));
+        assert!(slanted.len() > straight.len());
+        for grip in slanted.iter().filter(|g| g.bar.kind() != Slant::Straight) {
+            assert!(grip.bar.slant.unsigned_abs() <= 2);
+            let frets: Vec<usize> = grip.notes.iter().map(|note| note.fret).collect();
+            assert_eq!(Some(frets), grip.bar.frets(&grip.strings()));
+        }
+    }
+}
@@ -1,260 +1,1492 @@
-//! Copedent — pedal/lever definitions and helpers for a 10‑string pedal steel.
+//! Copedent — pedal/lever definitions and helpers for a pedal steel.
 //!
 //! This module models the copedent (pedal and lever changes) for a pedal-steel
 //! neck: which strings are raised or lowered and by how many semitones.
+//! Copedents are plain data and can be loaded from a TOML or JSON file; the
+//! built-in E9 copedent is used when no file is given.
 
-use strum_macros::EnumIter;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, fs, path::Path};
+use strum_macros::Display;
 
-const NUMBER_OF_STRINGS: usize = 10;
+/// The copedent used when no copedent file is given
+pub(crate) const DEFAULT_COPEDENT: &str = include_str!("../copedents/e9.toml");
+
+/// Name used for the position with no pedals or levers engaged
+pub const OPEN: &str = "Open";
+
+/// Suffix naming a control held at its half stop, e.g. "RKL½"
+pub const HALF_STOP: &str = "½";
+
+/// The most a string can be raised or lowered, in semitones: an octave
+pub const MAX_CHANGE: i8 = 12;
+
+/// How many neighbouring pedals one foot can hold down when the copedent does not say
+const DEFAULT_MAX_PEDALS_PER_FOOT: usize = 2;
 
 /// Represents a change in the copedent for a specific string
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct CopedentChange {
     /// The string number
     pub string: u8,
     /// The semitone change (+/-) as an integer
     pub semitone_change: i8,
+    /// Optional human readable description of the change, e.g. "B→C#"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The change at the control's half stop, e.g. 1 for a whole-tone raise
+    /// that can also be stopped a semitone up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_stop: Option<i8>,
 }
 
-/// Represents a change in the copedent for a specific position
-#[derive(Debug)]
+impl CopedentChange {
+    /// The change made with the control fully engaged, or held at its half stop.
+    /// Strings without a half stop always get the full change.
+    pub fn semitones(&self, half: bool) -> i8 {
+        match self.half_stop {
+            Some(half_stop) if half => half_stop,
+            _ => self.semitone_change,
+        }
+    }
+}
+
+/// The kind of control a player engages to change the tuning
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlKind {
+    #[strum(serialize = "pedal")]
+    Pedal,
+    #[strum(serialize = "knee lever")]
+    KneeLever,
+    #[strum(serialize = "vertical lever")]
+    VerticalLever,
+}
+
+/// The knee that pushes a lever
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Knee {
+    #[strum(serialize = "left knee")]
+    Left,
+    #[strum(serialize = "right knee")]
+    Right,
+}
+
+/// The foot that presses a pedal
+#[derive(Display, Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Foot {
+    #[default]
+    #[strum(serialize = "left foot")]
+    Left,
+    #[strum(serialize = "right foot")]
+    Right,
+}
+
+/// Represents a pedal or lever and the changes it makes when engaged
+#[derive(Debug, Clone)]
+pub struct Control {
+    /// The name of the control, e.g. "A" or "LKL"
+    pub name: String,
+    /// Whether the control is a pedal or a lever
+    pub kind: ControlKind,
+    /// The knee that pushes the lever; a knee can only push one lever at a time.
+    /// Pedals and levers without a knee are not limited.
+    pub knee: Option<Knee>,
+    /// The foot that presses the pedal. Levers have no foot.
+    pub foot: Option<Foot>,
+    /// The list of copedent changes for the control
+    pub changes: Vec<CopedentChange>,
+}
+
+impl Control {
+    /// Whether any change of the control can be held at a half stop
+    pub fn has_half_stop(&self) -> bool {
+        self.changes.iter().any(|change| change.half_stop.is_some())
+    }
+
+    /// The change the control makes to a string, counting from 1
+    pub fn change(&self, string: usize) -> Option<&CopedentChange> {
+        self.changes
+            .iter()
+            .find(|change| usize::from(change.string) == string)
+    }
+}
+
+/// Split a control name into the control and whether it is held at its half
+/// stop: "RKL½" and "RKL/2" are the half stop of RKL
+pub fn split_half_stop(name: &str) -> (&str, bool) {
+    name.strip_suffix(HALF_STOP)
+        .or_else(|| name.strip_suffix("/2"))
+        .map_or((name, false), |control| (control, true))
+}
+
+/// Whether two control names, possibly with a half stop, mean the same thing
+fn same_control(a: &str, b: &str) -> bool {
+    let (a, a_half) = split_half_stop(a);
+    let (b, b_half) = split_half_stop(b);
+    a.eq_ignore_ascii_case(b) && a_half == b_half
+}
+
+/// A split tuning: when all of `controls` are engaged, `string` gets this
+/// change instead of the sum of the individual changes
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Override {
+    /// The controls that must be engaged together; half stops end in "½"
+    pub controls: Vec<String>,
+    /// The string number
+    pub string: u8,
+    /// The semitone change (+/-) the string gets
+    pub semitone_change: i8,
+}
+
+impl Override {
+    /// Whether every control of the override is engaged in `position`
+    pub fn applies_to(&self, position: &[String]) -> bool {
+        self.controls
+            .iter()
+            .all(|name| position.iter().any(|engaged| same_control(name, engaged)))
+    }
+}
+
+/// Represents a full copedent: every pedal and lever and the changes they make
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "CopedentFile", into = "CopedentFile")]
 pub struct Copedent {
-    /// The list of copedent changes for the position
-    pub copedent_change: Vec<CopedentChange>,
+    /// Name of the copedent, e.g. "E9"
+    pub name: String,
+    /// Every pedal and lever, in the order they are defined
+    pub controls: Vec<Control>,
+    /// Rules saying which controls can be engaged together
+    pub constraints: Constraints,
+    /// Split tunings: combinations whose changes do not simply add up
+    pub overrides: Vec<Override>,
 }
 
-/// Represents the overall pedal and lever changes for a set of positions
-pub struct PedalAndLevers {
-    /// The list of copedent changes for the set of positions
-    pub copedent_change: Vec<u8>,
-}
-
-/// Represents a pedal or lever position on a pedal steel guitar
-#[derive(EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Position {
-    Open,
-    A,
-    B,
-    C,
-    D,
-    Lkl,
-    Lkv,
-    Lkr,
-    Rkl,
-    Rkr,
-}
-
-/// Get a list of possible pedal and lever combos for a pedal steel guitar
-pub fn possible_positions() -> Vec<Vec<Position>> {
-    vec![
-        vec![Position::Open],
-        vec![Position::A],
-        vec![Position::B],
-        vec![Position::C],
-        vec![Position::Lkl],
-        vec![Position::Lkv],
-        vec![Position::Lkr],
-        vec![Position::Rkl],
-        vec![Position::Rkr],
-        vec![Position::A, Position::B],
-        vec![Position::B, Position::C],
-        vec![Position::A, Position::Lkl],
-        vec![Position::B, Position::Lkr],
-        vec![Position::Lkv],
-        vec![Position::Rkl],
-        vec![Position::Rkr],
-    ]
-}
-
-/// Get the string representation of a position
-pub const fn position_string(position: &Position) -> &str {
-    match position {
-        Position::Open => "Open",
-        Position::A => "A",
-        Position::B => "B",
-        Position::C => "C",
-        Position::D => "D",
-        Position::Lkl => "LKL",
-        Position::Lkv => "LKV",
-        Position::Lkr => "LKR",
-        Position::Rkl => "RKL",
-        Position::Rkr => "RKR",
-    }
-}
-
-/// Get the copedent changes for a specific position
-#[allow(clippy::too_many_lines)]
-pub fn copedent_change(position: Position) -> Copedent {
-    match position {
-        Position::Open => Copedent {
-            copedent_change: vec![],
-        },
-        Position::A => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 10,
-                    semitone_change: 2,
-                },
-                CopedentChange {
-                    string: 5,
-                    semitone_change: 2,
-                },
-            ],
-        },
-        Position::B => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 6,
-                    semitone_change: 1,
-                },
-                CopedentChange {
-                    string: 3,
-                    semitone_change: 1,
-                },
-            ],
-        },
-        Position::C => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 5,
-                    semitone_change: 2,
-                },
-                CopedentChange {
-                    string: 4,
-                    semitone_change: 2,
-                },
-            ],
-        },
-        Position::D => Copedent {
-            copedent_change: vec![CopedentChange {
-                string: 1,
-                semitone_change: 2,
-            }],
-        },
-        Position::Lkl => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 8,
-                    semitone_change: 1,
-                },
-                CopedentChange {
-                    string: 4,
-                    semitone_change: 1,
-                },
-            ],
-        },
-        Position::Lkv => Copedent {
-            copedent_change: vec![CopedentChange {
-                string: 5,
-                semitone_change: -1,
-            }],
-        },
-        Position::Lkr => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 8,
-                    semitone_change: -1,
-                },
-                CopedentChange {
-                    string: 4,
-                    semitone_change: -1,
-                },
-            ],
-        },
-        Position::Rkl => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 1,
-                    semitone_change: -1,
-                },
-                CopedentChange {
-                    string: 6,
-                    semitone_change: -2,
-                },
-            ],
-        },
-        Position::Rkr => Copedent {
-            copedent_change: vec![
-                CopedentChange {
-                    string: 9,
-                    semitone_change: -1,
-                },
-                CopedentChange {
-                    string: 2,
-                    semitone_change: -1,
-                },
-            ],
-        },
+/// Ergonomic rules for a copedent, beyond one lever per knee
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Constraints {
+    /// Groups of controls that can never be engaged together, e.g. levers
+    /// that are rodded to the same crossbar
+    pub exclusive: Vec<Vec<String>>,
+    /// The most pedals one foot can hold down at once. Pedals held together
+    /// must sit next to each other under that foot.
+    pub max_pedals_per_foot: usize,
+    /// Whether a combination may need both feet on pedals. The right foot
+    /// usually stays on the volume pedal, so this is off unless the copedent
+    /// says otherwise.
+    pub allow_both_feet: bool,
+}
+
+impl Constraints {
+    /// No exclusive groups, two pedals per foot and one foot on the pedals
+    pub const fn new() -> Self {
+        Self {
+            exclusive: Vec::new(),
+            max_pedals_per_foot: DEFAULT_MAX_PEDALS_PER_FOOT,
+            allow_both_feet: false,
+        }
     }
 }
 
-/// Calculate the overall pedal and lever changes for a set of positions
-pub fn pedal_and_levers(positions: &[Position]) -> PedalAndLevers {
-    let mut copedent_offset = [0_u8; NUMBER_OF_STRINGS];
+impl Default for Constraints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    // For each position, get the copedent changes and add them to the offset
-    for position in positions {
-        let copedent = copedent_change(*position);
+/// The kind of a lever as written in a copedent file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum LeverKind {
+    #[default]
+    Knee,
+    Vertical,
+}
+
+impl LeverKind {
+    fn is_knee(&self) -> bool {
+        *self == Self::Knee
+    }
+}
+
+/// A pedal or lever as written in a copedent file
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct ControlFile {
+    name: String,
+    #[serde(default, skip_serializing_if = "LeverKind::is_knee")]
+    kind: LeverKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    knee: Option<Knee>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    foot: Option<Foot>,
+    #[serde(default)]
+    changes: Vec<CopedentChange>,
+}
+
+/// Guess the knee of a lever from the usual names, e.g. "LKL" or "RKR"
+fn knee_from_name(name: &str) -> Option<Knee> {
+    let name = name.to_ascii_uppercase();
+    if name.starts_with("LK") {
+        Some(Knee::Left)
+    } else if name.starts_with("RK") {
+        Some(Knee::Right)
+    } else {
+        None
+    }
+}
+
+/// The on-disk layout of a copedent: pedals and levers in separate lists.
+/// Copedents are written back out in the same layout, so `--format json`
+/// output can be loaded again.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct CopedentFile {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    pedals: Vec<ControlFile>,
+    #[serde(default)]
+    levers: Vec<ControlFile>,
+    #[serde(default)]
+    constraints: Constraints,
+    #[serde(default)]
+    overrides: Vec<Override>,
+}
+
+impl From<Copedent> for CopedentFile {
+    fn from(copedent: Copedent) -> Self {
+        let (pedals, levers): (Vec<Control>, Vec<Control>) = copedent
+            .controls
+            .into_iter()
+            .partition(|control| control.kind == ControlKind::Pedal);
+        let file = |control: Control| ControlFile {
+            name: control.name,
+            kind: match control.kind {
+                ControlKind::VerticalLever => LeverKind::Vertical,
+                ControlKind::Pedal | ControlKind::KneeLever => LeverKind::Knee,
+            },
+            knee: control.knee,
+            foot: control.foot,
+            changes: control.changes,
+        };
+
+        Self {
+            name: copedent.name,
+            pedals: pedals.into_iter().map(file).collect(),
+            levers: levers.into_iter().map(file).collect(),
+            constraints: copedent.constraints,
+            overrides: copedent.overrides,
+        }
+    }
+}
+
+impl TryFrom<CopedentFile> for Copedent {
+    type Error = CopedentError;
 
-        // Define the copedent offset
-        for change in &copedent.copedent_change {
-            copedent_offset[(change.string - 1) as usize] +=
-                change.semitone_change.rem_euclid(12) as u8;
+    fn try_from(file: CopedentFile) -> Result<Self, Self::Error> {
+        let pedals = file.pedals.into_iter().map(|pedal| Control {
+            name: pedal.name,
+            kind: ControlKind::Pedal,
+            knee: None,
+            foot: Some(pedal.foot.unwrap_or_default()),
+            changes: pedal.changes,
+        });
+        let levers = file.levers.into_iter().map(|lever| Control {
+            knee: lever.knee.or_else(|| knee_from_name(&lever.name)),
+            name: lever.name,
+            kind: match lever.kind {
+                LeverKind::Knee => ControlKind::KneeLever,
+                LeverKind::Vertical => ControlKind::VerticalLever,
+            },
+            foot: None,
+            changes: lever.changes,
+        });
+        let controls: Vec<Control> = pedals.chain(levers).collect();
+
+        for (i, control) in controls.iter().enumerate() {
+            if control.name.eq_ignore_ascii_case(OPEN)
+                || controls[..i]
+                    .iter()
+                    .any(|other| other.name.eq_ignore_ascii_case(&control.name))
+            {
+                return Err(CopedentError::DuplicateControl(control.name.clone()));
+            }
+            for (j, change) in control.changes.iter().enumerate() {
+                if control.changes[..j]
+                    .iter()
+                    .any(|other| other.string == change.string)
+                {
+                    return Err(CopedentError::DuplicateString {
+                        control: control.name.clone(),
+                        string: change.string,
+                    });
+                }
+            }
+        }
+
+        let copedent = Self {
+            name: file.name,
+            controls,
+            constraints: file.constraints,
+            overrides: file.overrides,
+        };
+        for name in copedent.constraints.exclusive.iter().flatten() {
+            if copedent.control(name).is_none() {
+                return Err(CopedentError::UnknownControl(name.clone()));
+            }
+        }
+        for name in copedent.overrides.iter().flat_map(|o| &o.controls) {
+            copedent.engaged_control(name)?;
+        }
+
+        let out_of_range = |change: i8| change.unsigned_abs() > MAX_CHANGE.unsigned_abs();
+        for control in &copedent.controls {
+            for change in &control.changes {
+                if out_of_range(change.semitone_change)
+                    || change.half_stop.is_some_and(out_of_range)
+                {
+                    return Err(CopedentError::ChangeOutOfRange {
+                        control: control.name.clone(),
+                        string: change.string,
+                    });
+                }
+            }
+        }
+        for split in &copedent.overrides {
+            if out_of_range(split.semitone_change) {
+                return Err(CopedentError::ChangeOutOfRange {
+                    control: position_name(&split.controls),
+                    string: split.string,
+                });
+            }
         }
+
+        Ok(copedent)
     }
+}
 
-    PedalAndLevers {
-        copedent_change: copedent_offset.to_vec(),
+/// Why a combination of controls cannot be played
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Two levers are pushed by the same knee
+    SameKnee {
+        knee: Knee,
+        first: String,
+        second: String,
+    },
+    /// Two controls from the same exclusive group are engaged
+    Exclusive { first: String, second: String },
+    /// One foot cannot hold down these pedals: too many, or not next to each other
+    OutOfReach { foot: Foot, pedals: Vec<String> },
+    /// Pedals are engaged under both feet
+    BothFeet,
+    /// A control is engaged more than once, e.g. fully and at its half stop
+    Repeated(String),
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SameKnee {
+                knee,
+                first,
+                second,
+            } => write!(f, "{first} and {second} are both pushed by the {knee}"),
+            Self::Exclusive { first, second } => {
+                write!(f, "{first} and {second} cannot be engaged together")
+            }
+            Self::OutOfReach { foot, pedals } => {
+                write!(f, "the {foot} cannot hold down {}", pedals.join(" and "))
+            }
+            Self::BothFeet => write!(f, "it needs both feet on the pedals"),
+            Self::Repeated(name) => write!(f, "{name} is engaged more than once"),
+        }
+    }
+}
+
+/// Errors that can occur while loading a copedent definition or combining its controls
+#[derive(Debug)]
+pub enum CopedentError {
+    /// The copedent file could not be read
+    Io(std::io::Error),
+    /// The copedent file is not a valid TOML copedent
+    Toml(toml::de::Error),
+    /// The copedent file is not a valid JSON copedent
+    Json(serde_json::Error),
+    /// Two controls share a name, or a control is named "Open"
+    DuplicateControl(String),
+    /// A control lists the same string more than once
+    DuplicateString { control: String, string: u8 },
+    /// A control name does not match any pedal or lever in the copedent
+    UnknownControl(String),
+    /// A control is asked for at its half stop but has none
+    NoHalfStop(String),
+    /// A combination of controls cannot be played
+    Unplayable {
+        position: String,
+        conflict: Conflict,
+    },
+    /// A control changes a string the guitar does not have
+    StringOutOfRange {
+        control: String,
+        string: u8,
+        string_count: usize,
+    },
+    /// A string is raised or lowered by more than an octave
+    ChangeOutOfRange { control: String, string: u8 },
+    /// The copedent file defines no pedals or levers
+    NoControls,
+}
+
+impl fmt::Display for CopedentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "could not read copedent: {e}"),
+            Self::Toml(e) => write!(f, "invalid TOML copedent: {e}"),
+            Self::Json(e) => write!(f, "invalid JSON copedent: {e}"),
+            Self::DuplicateControl(name) => write!(f, "control {name:?} is defined more than once"),
+            Self::DuplicateString { control, string } => {
+                write!(
+                    f,
+                    "control {control:?} changes string {string} more than once"
+                )
+            }
+            Self::UnknownControl(name) => write!(f, "no pedal or lever is named {name:?}"),
+            Self::NoHalfStop(name) => write!(f, "control {name:?} has no half stop"),
+            Self::NoControls => write!(f, "the copedent defines no pedals or levers"),
+            Self::Unplayable { position, conflict } => {
+                write!(f, "{position} is not playable: {conflict}")
+            }
+            Self::StringOutOfRange {
+                control,
+                string,
+                string_count,
+            } => write!(
+                f,
+                "control {control:?} changes string {string}, but the guitar has strings 1 to {string_count}"
+            ),
+            Self::ChangeOutOfRange { control, string } => write!(
+                f,
+                "{control} changes string {string} by more than {MAX_CHANGE} semitones"
+            ),
+        }
     }
 }
 
-/// Generate a name for a set of positions
-pub fn position_name(positions: &[Position]) -> String {
-    let mut name = String::new();
+impl std::error::Error for CopedentError {}
+
+impl Copedent {
+    /// Parse a copedent from a TOML string
+    pub fn from_toml_str(s: &str) -> Result<Self, CopedentError> {
+        toml::from_str(s).map_err(CopedentError::Toml)
+    }
+
+    /// Parse a copedent from a JSON string
+    pub fn from_json_str(s: &str) -> Result<Self, CopedentError> {
+        serde_json::from_str(s).map_err(CopedentError::Json)
+    }
+
+    /// Load a copedent from a file. Files ending in `.json` are parsed as JSON,
+    /// everything else as TOML. A file without any pedals or levers is
+    /// rejected, as it is most likely misspelt.
+    pub fn from_file(path: &Path) -> Result<Self, CopedentError> {
+        let contents = fs::read_to_string(path).map_err(CopedentError::Io)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+        let copedent = if is_json {
+            Self::from_json_str(&contents)?
+        } else {
+            Self::from_toml_str(&contents)?
+        };
+        if copedent.controls.is_empty() {
+            return Err(CopedentError::NoControls);
+        }
+        Ok(copedent)
+    }
+
+    /// A copedent with no pedals or levers, as on a lap steel
+    pub const fn open() -> Self {
+        Self {
+            name: String::new(),
+            controls: Vec::new(),
+            constraints: Constraints::new(),
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Find a control by name, ignoring case
+    pub fn control(&self, name: &str) -> Option<&Control> {
+        self.controls
+            .iter()
+            .find(|control| control.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Find the control an engaged name refers to, and whether it is held at
+    /// its half stop
+    fn engaged_control(&self, name: &str) -> Result<(&Control, bool), CopedentError> {
+        let (control_name, half) = split_half_stop(name);
+        let control = self
+            .control(control_name)
+            .ok_or_else(|| CopedentError::UnknownControl(name.to_string()))?;
+        if half && !control.has_half_stop() {
+            return Err(CopedentError::NoHalfStop(control.name.clone()));
+        }
+        Ok((control, half))
+    }
+
+    /// Iterate over the controls of a given kind
+    pub fn controls_of_kind(&self, kind: ControlKind) -> impl Iterator<Item = &Control> {
+        self.controls
+            .iter()
+            .filter(move |control| control.kind == kind)
+    }
+
+    /// The number of strings the copedent needs: the highest string any control changes
+    pub fn string_count(&self) -> usize {
+        self.controls
+            .iter()
+            .flat_map(|control| &control.changes)
+            .map(|change| usize::from(change.string))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check that every change refers to a string that exists on a guitar with
+    /// `string_count` strings
+    pub fn validate_strings(&self, string_count: usize) -> Result<(), CopedentError> {
+        for control in &self.controls {
+            for change in &control.changes {
+                if change.string == 0 || usize::from(change.string) > string_count {
+                    return Err(CopedentError::StringOutOfRange {
+                        control: control.name.clone(),
+                        string: change.string,
+                        string_count,
+                    });
+                }
+            }
+        }
+        for split in &self.overrides {
+            if split.string == 0 || usize::from(split.string) > string_count {
+                return Err(CopedentError::StringOutOfRange {
+                    control: position_name(&split.controls),
+                    string: split.string,
+                    string_count,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a combination of controls can be played: every control
+    /// exists, each knee pushes one lever, no two controls from an exclusive
+    /// group are engaged and the pedals are within reach of one foot.
+    pub fn validate_position(&self, position: &[String]) -> Result<(), CopedentError> {
+        let controls = position
+            .iter()
+            .map(|name| self.engaged_control(name).map(|(control, _)| control))
+            .collect::<Result<Vec<_>, _>>()?;
+        let unplayable = |conflict| CopedentError::Unplayable {
+            position: position_name(position),
+            conflict,
+        };
+
+        for (i, control) in controls.iter().enumerate() {
+            for other in &controls[..i] {
+                if control.name == other.name {
+                    return Err(unplayable(Conflict::Repeated(control.name.clone())));
+                }
+                if let Some(knee) = control.knee
+                    && other.knee == Some(knee)
+                {
+                    return Err(unplayable(Conflict::SameKnee {
+                        knee,
+                        first: other.name.clone(),
+                        second: control.name.clone(),
+                    }));
+                }
+                let exclusive = self.constraints.exclusive.iter().any(|group| {
+                    let in_group =
+                        |name: &str| group.iter().any(|member| member.eq_ignore_ascii_case(name));
+                    in_group(&control.name) && in_group(&other.name)
+                });
+                if exclusive {
+                    return Err(unplayable(Conflict::Exclusive {
+                        first: other.name.clone(),
+                        second: control.name.clone(),
+                    }));
+                }
+            }
+        }
+
+        let mut feet_used = 0;
+        for foot in [Foot::Left, Foot::Right] {
+            // Positions of the engaged pedals among the pedals under this foot
+            let under_foot: Vec<&Control> = self
+                .controls
+                .iter()
+                .filter(|control| control.foot == Some(foot))
+                .collect();
+            let held: Vec<usize> = under_foot
+                .iter()
+                .enumerate()
+                .filter(|(_, pedal)| controls.iter().any(|control| control.name == pedal.name))
+                .map(|(i, _)| i)
+                .collect();
+
+            if held.is_empty() {
+                continue;
+            }
+            feet_used += 1;
+            let neighbours = held.windows(2).all(|pair| pair[1] == pair[0] + 1);
+            if held.len() > self.constraints.max_pedals_per_foot || !neighbours {
+                return Err(unplayable(Conflict::OutOfReach {
+                    foot,
+                    pedals: held.iter().map(|&i| under_foot[i].name.clone()).collect(),
+                }));
+            }
+        }
+        if feet_used > 1 && !self.constraints.allow_both_feet {
+            return Err(unplayable(Conflict::BothFeet));
+        }
+
+        Ok(())
+    }
+
+    /// Get the copedent changes for a control. Unknown controls make no changes.
+    pub fn changes(&self, name: &str) -> &[CopedentChange] {
+        self.control(name)
+            .map_or(&[], |control| control.changes.as_slice())
+    }
+}
+
+impl Default for Copedent {
+    fn default() -> Self {
+        Self::from_toml_str(DEFAULT_COPEDENT).expect("built-in copedent is valid")
+    }
+}
+
+/// Limits used when generating pedal and lever combinations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CombinationLimits {
+    /// The most controls engaged at once
+    pub max_controls: usize,
+}
+
+impl Default for CombinationLimits {
+    fn default() -> Self {
+        Self { max_controls: 2 }
+    }
+}
+
+/// Generate every playable pedal and lever combination for a copedent, Open
+/// first and then ordered by the number of controls engaged.
+///
+/// A combination holds down a run of neighbouring pedals under each foot and
+/// at most one lever per knee, within the given limits, and passes
+/// [`Copedent::validate_position`]. Controls with a half stop appear both fully
+/// engaged and held at the half stop, e.g. "RKL" and "RKL½".
+pub fn possible_positions(copedent: &Copedent, limits: CombinationLimits) -> Vec<Vec<String>> {
+    let indices_where = |keep: &dyn Fn(&Control) -> bool| -> Vec<usize> {
+        (0..copedent.controls.len())
+            .filter(|&i| keep(&copedent.controls[i]))
+            .collect()
+    };
+
+    // Each group lists alternatives; a combination picks one alternative from every group
+    let mut groups: Vec<Vec<Vec<usize>>> = Vec::new();
+
+    // Each foot holds down nothing, or a run of neighbouring pedals
+    for foot in [Foot::Left, Foot::Right] {
+        let pedals = indices_where(&|control| control.foot == Some(foot));
+        let mut alternatives = vec![vec![]];
+        for run in 1..=copedent.constraints.max_pedals_per_foot.min(pedals.len()) {
+            alternatives.extend(pedals.windows(run).map(<[usize]>::to_vec));
+        }
+        groups.push(alternatives);
+    }
+
+    // Each knee pushes nothing, or one of its levers
+    for knee in [Knee::Left, Knee::Right] {
+        let levers = indices_where(&|control| control.knee == Some(knee));
+        groups.push(
+            std::iter::once(vec![])
+                .chain(levers.into_iter().map(|i| vec![i]))
+                .collect(),
+        );
+    }
+
+    // Levers without a knee can be used in any combination
+    for i in indices_where(&|control| control.kind != ControlKind::Pedal && control.knee.is_none())
+    {
+        groups.push(vec![vec![], vec![i]]);
+    }
+
+    let mut combinations: Vec<Vec<usize>> = vec![vec![]];
+    for group in &groups {
+        combinations = combinations
+            .iter()
+            .flat_map(|combination| {
+                group
+                    .iter()
+                    .map(move |alternative| [combination.as_slice(), alternative].concat())
+            })
+            .filter(|combination| combination.len() <= limits.max_controls)
+            .collect();
+    }
+    for combination in &mut combinations {
+        combination.sort_unstable();
+    }
+    combinations.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+    combinations
+        .into_iter()
+        .map(|combination| {
+            combination
+                .into_iter()
+                .map(|i| copedent.controls[i].name.clone())
+                .collect::<Vec<_>>()
+        })
+        .filter(|position| copedent.validate_position(position).is_ok())
+        .flat_map(|position| with_half_stops(copedent, &position))
+        .collect()
+}
+
+/// Every way to hold a combination: each control with a half stop is either
+/// fully engaged or held at the half stop
+fn with_half_stops(copedent: &Copedent, position: &[String]) -> Vec<Vec<String>> {
+    let mut positions = vec![vec![]];
+    for name in position {
+        let mut ways = vec![name.clone()];
+        if copedent.control(name).is_some_and(Control::has_half_stop) {
+            ways.push(format!("{name}{HALF_STOP}"));
+        }
+        positions = positions
+            .iter()
+            .flat_map(|held: &Vec<String>| {
+                ways.iter()
+                    .map(move |way| [held.as_slice(), std::slice::from_ref(way)].concat())
+            })
+            .collect();
+    }
+    positions
+}
+
+/// Represents the overall pedal and lever changes for a set of positions
+pub struct PedalAndLevers {
+    /// The signed semitone change for each string, string 1 first
+    pub copedent_change: Vec<i8>,
+}
+
+/// Calculate the overall pedal and lever changes for a set of positions on a
+/// guitar with `string_count` strings. Combinations that cannot be played are
+/// rejected (see [`Copedent::validate_position`]). Controls named with a
+/// trailing "½" use their half stop, and the copedent's overrides replace the
+/// summed change for split tunings. A string the combination moves by more
+/// than [`MAX_CHANGE`] is an error. Changes to strings the guitar does not
+/// have are ignored; use [`Copedent::validate_strings`] to reject them.
+pub fn pedal_and_levers(
+    copedent: &Copedent,
+    positions: &[String],
+    string_count: usize,
+) -> Result<PedalAndLevers, CopedentError> {
+    copedent.validate_position(positions)?;
+
+    // Summed wide so that no combination of changes can overflow
+    let mut copedent_offset = vec![0_i16; string_count];
+
+    // Strings are numbered from 1
+    let index = |string: u8| usize::from(string).checked_sub(1);
+
+    // For each position, get the copedent changes and add them to the offset
     for position in positions {
-        if !name.is_empty() {
-            name.push_str(" & ");
+        let (name, half) = split_half_stop(position);
+        for change in copedent.changes(name) {
+            if let Some(offset) = index(change.string).and_then(|i| copedent_offset.get_mut(i)) {
+                *offset += i16::from(change.semitones(half));
+            }
         }
-        name.push_str(position_string(position));
     }
-    name
+
+    // Split tunings replace the sum; the override naming the most controls wins
+    let mut overrides: Vec<&Override> = copedent
+        .overrides
+        .iter()
+        .filter(|split| split.applies_to(positions))
+        .collect();
+    overrides.sort_by_key(|split| split.controls.len());
+    for split in overrides {
+        if let Some(offset) = index(split.string).and_then(|i| copedent_offset.get_mut(i)) {
+            *offset = i16::from(split.semitone_change);
+        }
+    }
+
+    let copedent_change = copedent_offset
+        .into_iter()
+        .zip(1..)
+        .map(|(offset, string)| {
+            i8::try_from(offset)
+                .ok()
+                .filter(|change| change.unsigned_abs() <= MAX_CHANGE.unsigned_abs())
+                .ok_or_else(|| CopedentError::ChangeOutOfRange {
+                    control: position_name(positions),
+                    string,
+                })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(PedalAndLevers { copedent_change })
+}
+
+/// Parse a list of combinations such as "Open, A+B, LKR". Combinations are
+/// separated by commas and the controls in a combination by "+"; "Open" is
+/// the combination with nothing engaged. Every combination is checked with
+/// [`Copedent::validate_position`].
+pub fn parse_positions(copedent: &Copedent, s: &str) -> Result<Vec<Vec<String>>, CopedentError> {
+    s.split(',')
+        .map(|combination| {
+            let position: Vec<String> = combination
+                .split('+')
+                .map(str::trim)
+                .filter(|name| !name.is_empty() && !name.eq_ignore_ascii_case(OPEN))
+                .map(|name| {
+                    let (control, half) = split_half_stop(name);
+                    let control = copedent.control(control).map_or(control, |c| &c.name);
+                    if half {
+                        format!("{control}{HALF_STOP}")
+                    } else {
+                        control.to_string()
+                    }
+                })
+                .collect();
+            copedent.validate_position(&position)?;
+            Ok(position)
+        })
+        .collect()
+}
+
+/// How many pedals and levers have to move to get from one combination to
+/// another. Going between a half stop and the full change counts as one move.
+pub fn control_changes(from: &[String], to: &[String]) -> usize {
+    // Each control held, and whether only to its half stop
+    let held = |position: &[String]| -> HashMap<String, bool> {
+        position
+            .iter()
+            .map(|name| {
+                let (control, half) = split_half_stop(name);
+                (control.to_ascii_uppercase(), half)
+            })
+            .collect()
+    };
+    let (from, to) = (held(from), held(to));
+    let released = from
+        .keys()
+        .filter(|control| !to.contains_key(*control))
+        .count();
+    let moved = to
+        .iter()
+        .filter(|(control, half)| from.get(*control) != Some(half))
+        .count();
+    released + moved
+}
+
+/// Generate a name for a set of positions. No positions is the Open position.
+pub fn position_name(positions: &[String]) -> String {
+    if positions.is_empty() {
+        return OPEN.to_string();
+    }
+    positions.join(" & ")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::names;
 
     #[test]
     fn test_copedent_change() {
-        let result = copedent_change(Position::A);
-        assert_eq!(result.copedent_change.len(), 2);
-        assert_eq!(result.copedent_change[0].string, 10);
-        assert_eq!(result.copedent_change[0].semitone_change, 2);
-        assert_eq!(result.copedent_change[1].string, 5);
-        assert_eq!(result.copedent_change[1].semitone_change, 2);
+        let copedent = Copedent::default();
+        let result = copedent.changes("A");
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].string, 10);
+        assert_eq!(result[0].semitone_change, 2);
+        assert_eq!(result[1].string, 5);
+        assert_eq!(result[1].semitone_change, 2);
+    }
+
+    #[test]
+    fn test_copedent_from_toml() {
+        let copedent = Copedent::from_toml_str(
+            r#"
+            name = "Minimal"
+
+            [[pedals]]
+            name = "A"
+            changes = [{ string = 5, semitone_change = 2, label = "B→C#" }]
+
+            [[levers]]
+            name = "LKR"
+            changes = [{ string = 4, semitone_change = -1 }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(copedent.name, "Minimal");
+        assert_eq!(copedent.changes("A")[0].label.as_deref(), Some("B→C#"));
+        assert_eq!(copedent.changes("lkr")[0].semitone_change, -1);
+        assert!(copedent.changes("B").is_empty());
+        assert_eq!(
+            copedent.control("LKR").unwrap().kind,
+            ControlKind::KneeLever
+        );
+        assert_eq!(
+            possible_positions(&copedent, CombinationLimits::default()),
+            vec![
+                Vec::<String>::new(),
+                names(&["A"]),
+                names(&["LKR"]),
+                names(&["A", "LKR"])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copedent_from_json() {
+        let copedent = Copedent::from_json_str(
+            r#"{"pedals": [{"name": "B", "changes": [{"string": 3, "semitone_change": 1}]}]}"#,
+        )
+        .unwrap();
+
+        let result = pedal_and_levers(&copedent, &names(&["B"]), 10).unwrap();
+        assert_eq!(result.copedent_change, vec![0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_copedent_rejects_unknown_keys() {
+        for source in [
+            "[[pedal]]\nname = \"A\"",
+            "[[pedals]]\nname = \"A\"\nchange = []",
+            "[[pedals]]\nname = \"A\"\nchanges = [{ string = 5, semitone = 2 }]",
+            "[constraints]\nmax_pedals = 3",
+        ] {
+            let result = Copedent::from_toml_str(source);
+            assert!(matches!(result, Err(CopedentError::Toml(_))), "{source}");
+        }
+        assert!(matches!(
+            Copedent::from_json_str(r#"{"foo": 1}"#),
+            Err(CopedentError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_copedent_file_needs_controls() {
+        let path = std::env::temp_dir().join("pedal-steel-empty-copedent.json");
+        fs::write(&path, r#"{"name": "Empty"}"#).unwrap();
+        let result = Copedent::from_file(&path);
+        fs::remove_file(&path).ok();
+        assert!(matches!(result, Err(CopedentError::NoControls)));
+    }
+
+    #[test]
+    fn test_copedent_supports_any_controls() {
+        let copedent = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "P8"
+            changes = [{ string = 1, semitone_change = 1 }]
+
+            [[levers]]
+            name = "LKV2"
+            kind = "vertical"
+            changes = [{ string = 2, semitone_change = -2 }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(copedent.control("P8").unwrap().kind, ControlKind::Pedal);
+        assert_eq!(
+            copedent.control("LKV2").unwrap().kind,
+            ControlKind::VerticalLever
+        );
+        let result = pedal_and_levers(&copedent, &names(&["P8", "LKV2"]), 10).unwrap();
+        assert_eq!(result.copedent_change, vec![1, -2, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_copedent_rejects_duplicate_controls() {
+        let result = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+
+            [[levers]]
+            name = "a"
+            "#,
+        );
+        assert!(matches!(result, Err(CopedentError::Toml(_))));
+    }
+
+    #[test]
+    fn test_copedent_rejects_duplicate_strings() {
+        let result = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+            changes = [
+                { string = 5, semitone_change = 2 },
+                { string = 5, semitone_change = 1 },
+            ]
+            "#,
+        );
+        let Err(CopedentError::Toml(e)) = result else {
+            panic!("expected a TOML error, got {result:?}");
+        };
+        assert!(e.to_string().contains("changes string 5 more than once"));
+    }
+
+    #[test]
+    fn test_levers_get_a_knee() {
+        let copedent = Copedent::default();
+        assert_eq!(copedent.control("A").unwrap().knee, None);
+        assert_eq!(copedent.control("LKV").unwrap().knee, Some(Knee::Left));
+        assert_eq!(copedent.control("RKR").unwrap().knee, Some(Knee::Right));
+    }
+
+    #[test]
+    fn test_possible_positions_are_unique_and_playable() {
+        let copedent = Copedent::default();
+        let positions = possible_positions(&copedent, CombinationLimits::default());
+
+        assert_eq!(positions[0], Vec::<String>::new());
+        assert_eq!(positions.len(), 39);
+        for (i, position) in positions.iter().enumerate() {
+            assert!(!positions[..i].contains(position), "{position:?} repeated");
+            assert!(position.len() <= 2);
+        }
+        assert!(positions.contains(&names(&["D"])));
+        assert!(positions.contains(&names(&["A", "B"])));
+        assert!(positions.contains(&names(&["B", "LKR"])));
+        assert!(positions.contains(&names(&["LKL", "RKR"])));
+        assert!(!positions.contains(&names(&["LKL", "LKR"])));
+        assert!(!positions.contains(&names(&["A", "C"])));
+    }
+
+    #[test]
+    fn test_possible_positions_follow_limits() {
+        let mut copedent = Copedent::default();
+        copedent.constraints.max_pedals_per_foot = 1;
+        let limits = CombinationLimits { max_controls: 4 };
+        let positions = possible_positions(&copedent, limits);
+
+        // One of 4 pedals or none, one of 3 left knee levers or none, one of 2 right knee levers or none
+        assert_eq!(positions.len(), 5 * 4 * 3);
+        assert!(positions.contains(&names(&["C", "LKV", "RKL"])));
+        assert!(!positions.contains(&names(&["A", "B"])));
+    }
+
+    #[test]
+    fn test_validate_position() {
+        let copedent = Copedent::default();
+        assert!(copedent.validate_position(&[]).is_ok());
+        assert!(
+            copedent
+                .validate_position(&names(&["A", "B", "LKL"]))
+                .is_ok()
+        );
+
+        let error = copedent
+            .validate_position(&names(&["LKV", "LKR"]))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            CopedentError::Unplayable {
+                conflict: Conflict::SameKnee {
+                    knee: Knee::Left,
+                    ..
+                },
+                ..
+            }
+        ));
+
+        let error = copedent.validate_position(&names(&["A", "C"])).unwrap_err();
+        assert!(matches!(
+            error,
+            CopedentError::Unplayable {
+                conflict: Conflict::OutOfReach {
+                    foot: Foot::Left,
+                    ..
+                },
+                ..
+            }
+        ));
+        assert!(matches!(
+            copedent.validate_position(&names(&["E"])),
+            Err(CopedentError::UnknownControl(_))
+        ));
+    }
+
+    #[test]
+    fn test_copedent_constraints() {
+        let copedent = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "P1"
+            changes = [{ string = 1, semitone_change = 1 }]
+
+            [[pedals]]
+            name = "P2"
+            changes = [{ string = 2, semitone_change = 1 }]
+
+            [[pedals]]
+            name = "P3"
+            foot = "right"
+            changes = [{ string = 3, semitone_change = 1 }]
+
+            [[levers]]
+            name = "V1"
+            kind = "vertical"
+            changes = [{ string = 4, semitone_change = -1 }]
+
+            [constraints]
+            exclusive = [["P1", "V1"]]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(copedent.control("P3").unwrap().foot, Some(Foot::Right));
+        assert!(matches!(
+            copedent.validate_position(&names(&["P1", "V1"])),
+            Err(CopedentError::Unplayable {
+                conflict: Conflict::Exclusive { .. },
+                ..
+            })
+        ));
+        assert!(matches!(
+            copedent.validate_position(&names(&["P2", "P3"])),
+            Err(CopedentError::Unplayable {
+                conflict: Conflict::BothFeet,
+                ..
+            })
+        ));
+        assert_eq!(
+            possible_positions(&copedent, CombinationLimits { max_controls: 3 }),
+            vec![
+                Vec::<String>::new(),
+                names(&["P1"]),
+                names(&["P2"]),
+                names(&["P3"]),
+                names(&["V1"]),
+                names(&["P1", "P2"]),
+                names(&["P2", "V1"]),
+                names(&["P3", "V1"]),
+            ]
+        );
+
+        let mut copedent = copedent;
+        copedent.constraints.allow_both_feet = true;
+        assert!(copedent.validate_position(&names(&["P2", "P3"])).is_ok());
+    }
+
+    #[test]
+    fn test_constraints_must_name_controls() {
+        let result = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+
+            [constraints]
+            exclusive = [["A", "Z"]]
+            "#,
+        );
+        assert!(matches!(result, Err(CopedentError::Toml(_))));
+    }
+
+    #[test]
+    fn test_pedal_and_levers_rejects_unplayable() {
+        let copedent = Copedent::default();
+        assert!(pedal_and_levers(&copedent, &names(&["LKL", "LKR"]), 10).is_err());
+        assert!(pedal_and_levers(&copedent, &names(&["A", "B", "C"]), 10).is_err());
+    }
+
+    /// E9 RKL with a half stop on string 6, and a split where pedal A and
+    /// LKV leave string 5 at B instead of the summed C
+    const SPLIT_COPEDENT: &str = r#"
+        [[pedals]]
+        name = "A"
+        changes = [{ string = 5, semitone_change = 2 }]
+
+        [[levers]]
+        name = "LKV"
+        kind = "vertical"
+        changes = [{ string = 5, semitone_change = -1 }]
+
+        [[levers]]
+        name = "RKL"
+        changes = [
+            { string = 1, semitone_change = -1 },
+            { string = 6, semitone_change = -2, half_stop = -1 },
+        ]
+
+        [[overrides]]
+        controls = ["A", "LKV"]
+        string = 5
+        semitone_change = 0
+
+        [[overrides]]
+        controls = ["A", "LKV", "RKL½"]
+        string = 5
+        semitone_change = 1
+    "#;
+
+    #[test]
+    fn test_half_stops() {
+        let copedent = Copedent::from_toml_str(SPLIT_COPEDENT).unwrap();
+        assert!(copedent.control("RKL").unwrap().has_half_stop());
+        assert!(!copedent.control("A").unwrap().has_half_stop());
+        assert_eq!(split_half_stop("RKL½"), ("RKL", true));
+        assert_eq!(split_half_stop("RKL/2"), ("RKL", true));
+        assert_eq!(split_half_stop("RKL"), ("RKL", false));
+
+        let result = pedal_and_levers(&copedent, &names(&["RKL"]), 6).unwrap();
+        assert_eq!(result.copedent_change, vec![-1, 0, 0, 0, 0, -2]);
+        let result = pedal_and_levers(&copedent, &names(&["RKL½"]), 6).unwrap();
+        assert_eq!(result.copedent_change, vec![-1, 0, 0, 0, 0, -1]);
+
+        assert!(matches!(
+            pedal_and_levers(&copedent, &names(&["A½"]), 6),
+            Err(CopedentError::NoHalfStop(_))
+        ));
+        assert!(matches!(
+            copedent.validate_position(&names(&["RKL", "RKL½"])),
+            Err(CopedentError::Unplayable {
+                conflict: Conflict::Repeated(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_possible_positions_include_half_stops() {
+        let copedent = Copedent::from_toml_str(SPLIT_COPEDENT).unwrap();
+        let positions = possible_positions(&copedent, CombinationLimits { max_controls: 1 });
+        assert_eq!(
+            positions,
+            vec![
+                Vec::<String>::new(),
+                names(&["A"]),
+                names(&["LKV"]),
+                names(&["RKL"]),
+                names(&["RKL½"]),
+            ]
+        );
+
+        let positions = possible_positions(&copedent, CombinationLimits { max_controls: 3 });
+        assert!(positions.contains(&names(&["A", "LKV", "RKL½"])));
+        for position in &positions {
+            assert!(
+                pedal_and_levers(&copedent, position, 6).is_ok(),
+                "{position:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_overrides_replace_the_sum() {
+        let copedent = Copedent::from_toml_str(SPLIT_COPEDENT).unwrap();
+
+        let result = pedal_and_levers(&copedent, &names(&["A", "LKV"]), 6).unwrap();
+        assert_eq!(result.copedent_change, vec![0, 0, 0, 0, 0, 0]);
+
+        // The override naming the most controls wins
+        let result = pedal_and_levers(&copedent, &names(&["A", "LKV", "RKL½"]), 6).unwrap();
+        assert_eq!(result.copedent_change, vec![-1, 0, 0, 0, 1, -1]);
+
+        // The half stop override does not apply with the lever fully engaged
+        let result = pedal_and_levers(&copedent, &names(&["A", "LKV", "RKL"]), 6).unwrap();
+        assert_eq!(result.copedent_change, vec![-1, 0, 0, 0, 0, -2]);
+    }
+
+    #[test]
+    fn test_overrides_must_name_controls() {
+        let result = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+            changes = [{ string = 5, semitone_change = 2 }]
+
+            [[overrides]]
+            controls = ["A½"]
+            string = 5
+            semitone_change = 1
+            "#,
+        );
+        assert!(matches!(result, Err(CopedentError::Toml(_))));
+    }
+
+    #[test]
+    fn test_copedent_rejects_changes_past_an_octave() {
+        for change in [
+            "semitone_change = 100",
+            "semitone_change = -13",
+            "semitone_change = 2, half_stop = 20",
+        ] {
+            let result = Copedent::from_toml_str(&format!(
+                "[[pedals]]\nname = \"A\"\nchanges = [{{ string = 5, {change} }}]"
+            ));
+            assert!(matches!(result, Err(CopedentError::Toml(_))), "{change}");
+        }
+
+        let result = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+            changes = [{ string = 5, semitone_change = 2 }]
+
+            [[overrides]]
+            controls = ["A"]
+            string = 5
+            semitone_change = 24
+            "#,
+        );
+        assert!(matches!(result, Err(CopedentError::Toml(_))));
+    }
+
+    #[test]
+    fn test_pedal_and_levers_rejects_sums_past_an_octave() {
+        let copedent = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+            changes = [{ string = 5, semitone_change = 12 }]
+
+            [[pedals]]
+            name = "B"
+            changes = [{ string = 5, semitone_change = 12 }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            pedal_and_levers(&copedent, &names(&["A"]), 6)
+                .unwrap()
+                .copedent_change[4],
+            12
+        );
+        assert!(matches!(
+            pedal_and_levers(&copedent, &names(&["A", "B"]), 6),
+            Err(CopedentError::ChangeOutOfRange { string: 5, .. })
+        ));
     }
 
     #[test]
     fn test_pedal_and_levers_open() {
-        let result = pedal_and_levers(&[Position::Open]);
-        assert_eq!(result.copedent_change, vec![0; NUMBER_OF_STRINGS]);
+        let result = pedal_and_levers(&Copedent::default(), &[], 10).unwrap();
+        assert_eq!(result.copedent_change, vec![0; 10]);
     }
 
     #[test]
     fn test_pedal_and_levers_a() {
-        let result = pedal_and_levers(&[Position::A]);
+        let result = pedal_and_levers(&Copedent::default(), &names(&["A"]), 10).unwrap();
         assert_eq!(result.copedent_change, vec![0, 0, 0, 0, 2, 0, 0, 0, 0, 2]);
     }
 
     #[test]
     fn test_pedal_and_levers_a_b() {
-        let result = pedal_and_levers(&[Position::A, Position::B]);
+        let result = pedal_and_levers(&Copedent::default(), &names(&["A", "B"]), 10).unwrap();
         assert_eq!(result.copedent_change, vec![0, 0, 1, 0, 2, 1, 0, 0, 0, 2]);
     }
 
+    #[test]
+    fn test_pedal_and_levers_lowers_are_negative() {
+        let result = pedal_and_levers(&Copedent::default(), &names(&["RKL"]), 10).unwrap();
+        assert_eq!(result.copedent_change, vec![-1, 0, 0, 0, 0, -2, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_pedal_and_levers_raise_and_lower_combine() {
+        let result = pedal_and_levers(&Copedent::default(), &names(&["C", "LKV"]), 10).unwrap();
+        assert_eq!(result.copedent_change, vec![0, 0, 0, 2, 1, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_pedal_and_levers_follows_string_count() {
+        let copedent = Copedent::default();
+        let result = pedal_and_levers(&copedent, &names(&["A"]), 12).unwrap();
+        assert_eq!(
+            result.copedent_change,
+            vec![0, 0, 0, 0, 2, 0, 0, 0, 0, 2, 0, 0]
+        );
+
+        let result = pedal_and_levers(&copedent, &names(&["A"]), 6).unwrap();
+        assert_eq!(result.copedent_change, vec![0, 0, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_validate_strings() {
+        let copedent = Copedent::default();
+        assert_eq!(copedent.string_count(), 10);
+        assert!(copedent.validate_strings(10).is_ok());
+        assert!(copedent.validate_strings(12).is_ok());
+
+        let error = copedent.validate_strings(6).unwrap_err();
+        assert!(matches!(
+            error,
+            CopedentError::StringOutOfRange {
+                string_count: 6,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_validate_strings_rejects_string_zero() {
+        let copedent = Copedent::from_toml_str(
+            r#"
+            [[pedals]]
+            name = "A"
+            changes = [{ string = 0, semitone_change = 1 }]
+            "#,
+        )
+        .unwrap();
+        assert!(copedent.validate_strings(10).is_err());
+    }
+
+    #[test]
+    fn test_parse_positions() {
+        let copedent = Copedent::default();
+        assert_eq!(
+            parse_positions(&copedent, "Open, a+b, LKR").unwrap(),
+            vec![Vec::<String>::new(), names(&["A", "B"]), names(&["LKR"])]
+        );
+        assert!(matches!(
+            parse_positions(&copedent, "A, LKL+LKR"),
+            Err(CopedentError::Unplayable { .. })
+        ));
+        assert!(matches!(
+            parse_positions(&copedent, "Z"),
+            Err(CopedentError::UnknownControl(_))
+        ));
+    }
+
+    #[test]
+    fn test_control_changes() {
+        assert_eq!(control_changes(&[], &[]), 0);
+        assert_eq!(control_changes(&names(&["A", "B"]), &names(&["B", "A"])), 0);
+        assert_eq!(control_changes(&[], &names(&["A", "B"])), 2);
+        assert_eq!(control_changes(&names(&["A", "B"]), &names(&["B", "C"])), 2);
+        assert_eq!(control_changes(&names(&["RKL½"]), &names(&["RKL"])), 1);
+        assert_eq!(control_changes(&names(&["lkr"]), &names(&["LKR"])), 0);
+    }
+
     #[test]
     fn test_position_name() {
-        let name = position_name(&[Position::A, Position::B, Position::Lkr]);
+        let name = position_name(&names(&["A", "B", "LKR"]));
         assert_eq!(name, "A & B & LKR");
+        assert_eq!(position_name(&[]), "Open");
     }
 }
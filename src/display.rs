@@ -4,87 +4,473 @@
 //! chord positions for a pedal-steel neck.
 
 use crate::{
-    copedent::{Position, copedent_change, position_name, position_string},
-    guitar::{Guitar, NeckPositions, frets_with_all_chord_tones, identify_notes_on_neck},
-    tunings::tuning,
+    chords::{ChordMatch, Grip, chord_name},
+    copedent::{
+        ControlKind, Copedent, CopedentChange, CopedentError, HALF_STOP, pedal_and_levers,
+        position_name,
+    },
+    guitar::{
+        Bar, FretRange, Guitar, NeckPositions, Slant, frets_with_all_chord_tones, inlay_dots,
+        notes_on_neck,
+    },
+    progression::Step,
+    spelling::{Interval, chord_interval, chord_notes, scale_notes, spell_change},
+    tunings::AbsolutePitch,
+    voice_leading::VoiceLeading,
 };
 use rust_music_theory::{
     chord::Chord,
-    note::{Note, Notes, Pitch},
+    note::{Note, Pitch},
     scale::Scale,
 };
-use std::fmt::Write;
-use strum::IntoEnumIterator;
+use serde::Serialize;
+use std::{
+    fmt::{self, Write},
+    io::{self, IsTerminal},
+};
+use strum_macros::{Display, EnumString};
+
+/// What is written on the neck diagram for each note
+#[derive(Display, EnumString, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Label {
+    /// Note names, e.g. "G#"
+    #[default]
+    Notes,
+    /// Degrees from the root, e.g. "R", "b3", "b7", "9"
+    Degrees,
+    /// Interval names from the root, e.g. "P1", "m3", "m7", "M9"
+    Intervals,
+    /// Nashville numbers from the root, e.g. "1", "b3", "b7", "2"
+    Nashville,
+}
+
+/// How a command writes its output
+#[derive(Display, EnumString, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Format {
+    /// Tables and neck diagrams for reading in a terminal
+    #[default]
+    Text,
+    /// JSON for scripts; see [`crate::report`] for the layout
+    Json,
+    /// SVG fretboard drawings; see [`crate::svg`]
+    Svg,
+    /// A single HTML page with every combination; see [`crate::html`]
+    Html,
+}
+
+/// When neck diagrams are drawn in colour
+#[derive(Display, EnumString, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ColorChoice {
+    /// Colour when writing to a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to write colour codes to stdout
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+        }
+    }
+}
+
+/// ANSI colour of the root of a chord or scale: bold red
+const ROOT_COLOR: &str = "1;31";
+/// ANSI colour of thirds: yellow
+const THIRD_COLOR: &str = "33";
+/// ANSI colour of fifths: cyan
+const FIFTH_COLOR: &str = "36";
+/// ANSI colour of every other note, sevenths and extensions: magenta
+const EXTENSION_COLOR: &str = "35";
+/// Notes changed by the engaged pedals and levers are shown in reverse video
+const ALTERED: &str = "7";
+/// Shading behind the numbers of frets with inlays
+const MARKED_FRET: &str = "100";
+
+/// The note the labels on a neck diagram are measured from
+pub enum Root<'a> {
+    /// The tonic of a scale or the first of a list of notes
+    Note(Pitch),
+    /// The root of a chord, whose extensions are labelled above the octave
+    Chord(&'a Chord),
+}
+
+impl Root<'_> {
+    /// How `note` relates to the root
+    pub fn interval(&self, note: Pitch) -> Interval {
+        match self {
+            Self::Note(root) => Interval::between(*root, note),
+            Self::Chord(chord) => chord_interval(chord, note),
+        }
+    }
+}
+
+/// Options controlling how notes are drawn
+#[derive(Debug, Clone, Default)]
+pub struct DisplayOptions {
+    /// Show the octave of every note, e.g. "G#4" instead of "G#"
+    pub octaves: bool,
+    /// The frets drawn on neck diagrams
+    pub frets: FretRange,
+    /// What each note on a neck diagram is labelled with
+    pub label: Label,
+    /// Colour neck diagrams with ANSI escape codes
+    pub color: bool,
+}
+
+impl DisplayOptions {
+    /// Format a note, with its octave when requested
+    pub fn note(&self, note: &AbsolutePitch) -> String {
+        if self.octaves {
+            note.to_string()
+        } else {
+            note.pitch.to_string()
+        }
+    }
+
+    /// Label a note on the neck diagram
+    pub fn label(&self, position: &NeckPositions, root: &Root) -> String {
+        match self.label {
+            Label::Notes if self.octaves => position.absolute_pitch().to_string(),
+            Label::Notes => position.note_name.clone(),
+            Label::Degrees => root.interval(position.pitch).degree_name(),
+            Label::Intervals => root.interval(position.pitch).name(),
+            Label::Nashville => root.interval(position.pitch).nashville(),
+        }
+    }
+
+    /// Colour `text` with the ANSI `code` when colour is on
+    fn paint(&self, text: &str, code: Option<&str>) -> String {
+        match code {
+            Some(code) if self.color => format!("\x1b[{code}m{text}\x1b[0m"),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Right-align `text` in a cell `width` wide, coloured with the ANSI
+    /// `code` when colour is on. The padding stays outside the colour so
+    /// shading and reverse video only cover the text.
+    fn cell(&self, text: &str, width: usize, code: Option<&str>) -> String {
+        let padding = " ".repeat(width.saturating_sub(text.chars().count()));
+        format!("{padding}{}", self.paint(text, code))
+    }
+
+    /// Name each string by its number and open note, and the note `changes`
+    /// takes it to, e.g. "4 E" or "5 B→C#"
+    fn string_names(&self, guitar: &Guitar, changes: &[i8]) -> Vec<String> {
+        let width = guitar.tuning.len().to_string().len();
+        guitar
+            .tuning
+            .iter()
+            .enumerate()
+            .map(|(i, open)| {
+                let change = changes.get(i).copied().unwrap_or_default();
+                let mut name = format!("{:>width$} {}", i + 1, self.note(open));
+                if change != 0 {
+                    let changed = AbsolutePitch {
+                        pitch: spell_change(open.pitch, change),
+                        midi: open.midi.saturating_add_signed(change),
+                    };
+                    write!(name, "\u{2192}{}", self.note(&changed)).ok();
+                }
+                name
+            })
+            .collect()
+    }
+
+    /// Width of one fret on the neck diagram
+    fn cell_width(&self) -> usize {
+        if self.octaves && self.label == Label::Notes {
+            4
+        } else {
+            3
+        }
+    }
+}
 
 /// Print the tuning of the guitar to the console
-pub fn print_tuning(tuning: &[Pitch]) {
+pub fn print_tuning(tuning: &[AbsolutePitch], options: &DisplayOptions) {
     tuning
         .iter()
         .enumerate()
-        .for_each(|(i, p)| println!("{:2} {}", i + 1, format_args!("{}", p)));
+        .for_each(|(i, p)| println!("{:2} {}", i + 1, options.note(p)));
+}
+
+/// Print a report as pretty-printed JSON
+pub fn print_json<T: Serialize>(report: &T) {
+    match serde_json::to_string_pretty(report) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("Error writing JSON: {e}"),
+    }
+}
+
+/// The symbol for a change on the copedent chart: "+" and "++" for a half
+/// and whole step up, "-" and "--" for down, the signed number of semitones
+/// for anything else, and a trailing "½" when the change has a half stop
+fn change_symbol(change: &CopedentChange) -> String {
+    let mut symbol = match change.semitone_change {
+        2 => "++".to_string(),
+        1 => "+".to_string(),
+        -1 => "-".to_string(),
+        -2 => "--".to_string(),
+        semitones => format!("{semitones:+}"),
+    };
+    if change.half_stop.is_some() {
+        symbol.push_str(HALF_STOP);
+    }
+    symbol
 }
 
-/// Print the copedent table to the console
-pub fn print_copedent() {
-    let positions: Vec<Position> = Position::iter().collect();
-    let string_count = 10;
+/// Print the copedent table for a guitar with `string_count` strings to the console
+pub fn print_copedent(copedent: &Copedent, string_count: usize) {
+    let width = copedent
+        .controls
+        .iter()
+        .flat_map(|control| {
+            let symbols = control
+                .changes
+                .iter()
+                .map(|change| change_symbol(change).chars().count());
+            symbols.chain([control.name.len()])
+        })
+        .map(|len| len + 1)
+        .max()
+        .unwrap_or(0)
+        .max(4);
+
+    if !copedent.name.is_empty() {
+        println!("{}", copedent.name);
+    }
 
     // Print header
     print!("{:>4}", "");
-    for pos in &positions {
-        if pos != &Position::Open {
-            print!("{:>4}", position_string(pos));
-        }
+    for control in &copedent.controls {
+        print!("{:>width$}", control.name);
     }
     println!();
 
-    // For each string (1 to 10)
+    // For each string
     for string in 1..=string_count {
         print!("{string:>4}");
-        for pos in &positions {
-            if pos != &Position::Open {
-                let copedent = copedent_change(*pos);
-                let mut symbol = String::from("   ");
-                for change in &copedent.copedent_change {
-                    if change.string == string {
-                        symbol = match change.semitone_change {
-                            2 => " ++",
-                            1 => "  +",
-                            -1 => "  -",
-                            -2 => " --",
-                            _ => "   ",
-                        }
-                        .to_string();
-                    }
-                }
-                print!("{symbol:>4}");
-            }
+        for control in &copedent.controls {
+            let symbol = control
+                .change(string)
+                .map(change_symbol)
+                .unwrap_or_default();
+            print!("{symbol:>width$}");
         }
         println!();
     }
+
+    // Print the kind of each control and its labelled changes
+    for control in &copedent.controls {
+        let labels: Vec<_> = control
+            .changes
+            .iter()
+            .filter_map(|change| {
+                change
+                    .label
+                    .as_ref()
+                    .map(|label| format!("{} {label}", change.string))
+            })
+            .collect();
+        print!("{:>width$}: {}", control.name, control.kind);
+        if let Some(knee) = control.knee {
+            print!(", {knee}");
+        }
+        if let Some(foot) = control.foot {
+            print!(", {foot}");
+        }
+        if !labels.is_empty() {
+            print!(" ({})", labels.join(", "));
+        }
+        let half_stops: Vec<_> = control
+            .changes
+            .iter()
+            .filter_map(|change| {
+                change
+                    .half_stop
+                    .map(|half_stop| format!("{} {half_stop:+}", change.string))
+            })
+            .collect();
+        if !half_stops.is_empty() {
+            print!(
+                ", half stop {}{HALF_STOP} ({})",
+                control.name,
+                half_stops.join(", ")
+            );
+        }
+        println!();
+    }
+
+    // Print the split tunings that replace the summed changes
+    for split in &copedent.overrides {
+        println!(
+            "{}: string {} {:+}",
+            position_name(&split.controls),
+            split.string,
+            split.semitone_change
+        );
+    }
+
+    // Print the combinations the copedent rules out
+    for group in &copedent.constraints.exclusive {
+        println!("Never together: {}", group.join(", "));
+    }
+    if copedent
+        .controls_of_kind(ControlKind::Pedal)
+        .next()
+        .is_some()
+    {
+        print!(
+            "Up to {} neighbouring pedals per foot",
+            copedent.constraints.max_pedals_per_foot
+        );
+        if !copedent.constraints.allow_both_feet {
+            print!(", one foot on the pedals");
+        }
+        println!();
+    }
+}
+
+/// The inlay marking a fret on the fretboard: one dot, two at the octaves
+fn fret_marker(fret: usize) -> &'static str {
+    match inlay_dots(fret) {
+        2 => "••",
+        1 => "•",
+        _ => "",
+    }
+}
+
+/// The colour of a note on the neck: the root, thirds, fifths and everything
+/// else each have their own, and notes the pedals and levers change are reversed
+fn note_color(guitar: &Guitar, position: &NeckPositions, root: &Root) -> String {
+    let color = match root.interval(position.pitch).degree {
+        1 => ROOT_COLOR,
+        3 => THIRD_COLOR,
+        5 => FIFTH_COLOR,
+        _ => EXTENSION_COLOR,
+    };
+    if position.is_altered(guitar) {
+        format!("{color};{ALTERED}")
+    } else {
+        color.to_string()
+    }
+}
+
+/// Errors that can occur while printing a neck diagram
+#[derive(Debug)]
+pub enum DisplayError {
+    /// The pedals and levers cannot be played together
+    Copedent(CopedentError),
+    /// The diagram could not be written out
+    Format(fmt::Error),
+}
+
+impl fmt::Display for DisplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Copedent(e) => write!(f, "{e}"),
+            Self::Format(e) => write!(f, "Error printing neck positions: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DisplayError {}
+
+impl From<CopedentError> for DisplayError {
+    fn from(e: CopedentError) -> Self {
+        Self::Copedent(e)
+    }
+}
+
+impl From<fmt::Error> for DisplayError {
+    fn from(e: fmt::Error) -> Self {
+        Self::Format(e)
+    }
 }
 
+/// Find `notes` on the neck in one position, with the change the position
+/// makes to each string
+fn find_on_neck(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    notes: &[Note],
+) -> Result<(Vec<NeckPositions>, Vec<i8>), CopedentError> {
+    let changes = pedal_and_levers(copedent, position, guitar.tuning.len())?.copedent_change;
+    Ok((notes_on_neck(guitar, &changes, notes), changes))
+}
+
+/// Draw notes on the neck. Each string row starts with the string number and
+/// its open note, followed by the note the engaged pedals and levers take it
+/// to when `changes` moves it, e.g. "5 B→C#".
 fn print_neck_positions(
     guitar: &Guitar,
     positions: &[NeckPositions],
     position_name: Option<&str>,
-) -> Result<(), std::fmt::Error> {
+    changes: &[i8],
+    root: &Root,
+    label: &dyn Fn(&NeckPositions) -> String,
+    options: &DisplayOptions,
+) -> fmt::Result {
+    // Widen the frets when a label does not fit, e.g. "b13"
+    let width = positions
+        .iter()
+        .map(|pos| label(pos).chars().count() + 1)
+        .fold(options.cell_width(), usize::max);
+    let names = options.string_names(guitar, changes);
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let indent = " ".repeat(name_width);
     println!("{}", guitar.name);
     if let Some(name) = position_name {
         println!(" {name}");
     }
-    for i in 0..12 {
-        print!("{i:>2} ");
-    }
-    println!();
+    let header: String = options
+        .frets
+        .frets()
+        .map(|i| {
+            let shade = (inlay_dots(i) > 0).then_some(MARKED_FRET);
+            format!("{} ", options.cell(&i.to_string(), width - 1, shade))
+        })
+        .collect();
+    println!("{indent}{header}");
+    let markers: String = options
+        .frets
+        .frets()
+        .map(|i| format!("{:>w$} ", fret_marker(i), w = width - 1))
+        .collect();
+    println!("{indent}{}", markers.trim_end());
 
-    for i in 0..guitar.tuning.len() {
-        let mut row = String::new();
-        for j in 0..12 {
+    for (i, name) in names.iter().enumerate() {
+        // Strings the pedals and levers change stand out in colour
+        let padding = " ".repeat(name_width - name.chars().count());
+        let code = changes
+            .get(i)
+            .is_some_and(|&change| change != 0)
+            .then_some(ALTERED);
+        let mut row = format!("{}{padding}", options.paint(name, code));
+        for j in options.frets.frets() {
             if let Some(pos) = positions.iter().find(|p| p.string == i && p.fret == j) {
-                write!(row, "{:>3}", pos.note_name)?;
+                let color = note_color(guitar, pos, root);
+                write!(row, "{}", options.cell(&label(pos), width, Some(&color)))?;
             } else {
-                write!(row, "{:>3}", " --")?;
+                write!(row, "{:>width$}", "--")?;
             }
         }
         println!("{row}");
@@ -94,40 +480,313 @@ fn print_neck_positions(
 }
 
 /// Print the chord positions to the console
-pub fn print_chord(guitar: &Guitar, position: &[Position], chord: &Chord) {
-    let neck_positions = identify_notes_on_neck(guitar, position, &chord.notes());
+pub fn print_chord(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    chord: &Chord,
+    options: &DisplayOptions,
+) -> Result<(), DisplayError> {
+    let (neck_positions, changes) = find_on_neck(guitar, copedent, position, &chord_notes(chord))?;
 
-    if let Err(e) = print_neck_positions(guitar, &neck_positions, Some(&position_name(position))) {
-        eprintln!("Error printing neck positions: {e}");
-    }
+    print_neck_positions(
+        guitar,
+        &neck_positions,
+        Some(&position_name(position)),
+        &changes,
+        &Root::Chord(chord),
+        &|pos| options.label(pos, &Root::Chord(chord)),
+        options,
+    )?;
+    Ok(())
 }
 
 /// Print the chord positions on a pedal steel guitar to the console
-pub fn print_chord_on_pedal_steel(guitar: &Guitar, position: &[Position], chord: &Chord) {
-    let neck_positions = identify_notes_on_neck(guitar, position, &chord.notes());
+pub fn print_chord_on_pedal_steel(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    chord: &Chord,
+    options: &DisplayOptions,
+) -> Result<(), DisplayError> {
+    let (neck_positions, changes) = find_on_neck(guitar, copedent, position, &chord_notes(chord))?;
     let frets = frets_with_all_chord_tones(&neck_positions, chord);
 
-    if let Err(e) = print_neck_positions(guitar, &frets, Some(&position_name(position))) {
-        eprintln!("Error printing neck positions: {e}");
-    }
+    print_neck_positions(
+        guitar,
+        &frets,
+        Some(&position_name(position)),
+        &changes,
+        &Root::Chord(chord),
+        &|pos| options.label(pos, &Root::Chord(chord)),
+        options,
+    )?;
+    Ok(())
 }
 
 /// Print the scale positions to the console
-pub fn print_scale(guitar: &Guitar, position: &[Position], scale: &Scale) {
-    let neck_positions = identify_notes_on_neck(guitar, position, &scale.notes());
+pub fn print_scale(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    scale: &Scale,
+    options: &DisplayOptions,
+) -> Result<(), DisplayError> {
+    let (neck_positions, changes) = find_on_neck(guitar, copedent, position, &scale_notes(scale))?;
 
-    if let Err(e) = print_neck_positions(guitar, &neck_positions, Some(&position_name(position))) {
-        eprintln!("Error printing neck positions: {e}");
-    }
+    print_neck_positions(
+        guitar,
+        &neck_positions,
+        Some(&position_name(position)),
+        &changes,
+        &Root::Note(scale.tonic),
+        &|pos| options.label(pos, &Root::Note(scale.tonic)),
+        options,
+    )?;
+    Ok(())
 }
 
 /// Print the notes on the guitar neck for a given position and notes
-pub fn print_notes_on_neck(guitar: &Guitar, position: &[Position], notes: &str) {
-    let pitch_list = tuning(notes);
-    let note_list: Vec<Note> = pitch_list.iter().map(|p| Note::new(*p, 0)).collect();
-    let neck_positions = identify_notes_on_neck(guitar, position, &note_list);
+pub fn print_notes_on_neck(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    position: &[String],
+    notes: &[Pitch],
+    options: &DisplayOptions,
+) -> Result<(), DisplayError> {
+    // Labels count from the first note given
+    let Some(&root) = notes.first() else {
+        return Ok(());
+    };
+    let note_list: Vec<Note> = notes.iter().map(|p| Note::new(*p, 0)).collect();
+    let (neck_positions, changes) = find_on_neck(guitar, copedent, position, &note_list)?;
+
+    print_neck_positions(
+        guitar,
+        &neck_positions,
+        Some(&position_name(position)),
+        &changes,
+        &Root::Note(root),
+        &|pos| options.label(pos, &Root::Note(root)),
+        options,
+    )?;
+    Ok(())
+}
+
+/// Spell a MIDI note the way the first chord found spells it
+fn spell_midi(midi: u8, matches: &[ChordMatch]) -> AbsolutePitch {
+    let pitch = matches
+        .first()
+        .and_then(|m| {
+            chord_notes(&m.chord)
+                .into_iter()
+                .map(|note| note.pitch)
+                .find(|pitch| pitch.into_u8() == midi % 12)
+        })
+        .unwrap_or_else(|| Pitch::from_u8(midi));
+    AbsolutePitch { pitch, midi }
+}
+
+/// Print the notes a grip sounds in one position and the chords they make
+pub fn print_chord_matches(
+    position: &[String],
+    midi: &[u8],
+    matches: &[ChordMatch],
+    options: &DisplayOptions,
+) {
+    let notes: Vec<String> = midi
+        .iter()
+        .map(|&midi| options.note(&spell_midi(midi, matches)))
+        .collect();
+
+    println!("{}: {}", position_name(position), notes.join(", "));
+    if matches.is_empty() {
+        println!("  no chord");
+    }
+    for m in matches {
+        println!("  {m}");
+    }
+}
+
+/// Print the grips found for a chord in one position, best first
+pub fn print_grips(
+    guitar: &Guitar,
+    position: &[String],
+    chord: &Chord,
+    grips: &[Grip],
+    options: &DisplayOptions,
+) {
+    let root = Root::Chord(chord);
+    println!("{}", guitar.name);
+    println!(" {}", position_name(position));
+    if grips.is_empty() {
+        println!("  no grips");
+        return;
+    }
+
+    println!("{:>5}  {:<14}notes", "fret", "strings");
+    for grip in grips {
+        let strings: Vec<String> = grip.strings().iter().map(ToString::to_string).collect();
+        let notes: Vec<String> = grip
+            .notes
+            .iter()
+            .map(|note| options.label(note, &root))
+            .collect();
+        let missing: Vec<String> = grip
+            .missing
+            .iter()
+            .map(|&pitch| root.interval(pitch).degree_name())
+            .collect();
+        let mut remark = if grip.is_complete() {
+            "complete".to_string()
+        } else {
+            format!("no {}", missing.join(", "))
+        };
+        if grip.root_in_bass {
+            remark.push_str(", root in the bass");
+        }
+        if grip.bar.kind() != Slant::Straight {
+            write!(remark, ", {}", grip.bar.kind()).ok();
+        }
+        println!(
+            "{:>5}  {:<14}{:<20}{remark}",
+            grip.bar.to_string(),
+            strings.join(", "),
+            notes.join(", ")
+        );
+    }
+}
+
+/// Draw a grip on the neck. Notes under a slanted bar are marked with the
+/// way the bar leans on the diagram: "/" for a forward slant and "\" for a
+/// reverse slant.
+pub fn print_grip_diagram(
+    guitar: &Guitar,
+    position: &[String],
+    chord: &Chord,
+    grip: &Grip,
+    options: &DisplayOptions,
+) -> Result<(), DisplayError> {
+    let root = Root::Chord(chord);
+    let mark = match grip.bar.kind() {
+        Slant::Straight => "",
+        Slant::Forward => "/",
+        Slant::Reverse => "\\",
+    };
+    let name = format!(
+        "{}, bar at {} ({})",
+        position_name(position),
+        grip.bar,
+        grip.bar.kind()
+    );
 
-    if let Err(e) = print_neck_positions(guitar, &neck_positions, Some(&position_name(position))) {
-        eprintln!("Error printing neck positions: {e}");
+    print_neck_positions(
+        guitar,
+        &grip.notes,
+        Some(&name),
+        &grip.changes,
+        &root,
+        &|pos| format!("{mark}{}", options.label(pos, &root)),
+        options,
+    )?;
+    Ok(())
+}
+
+/// Print a planned progression step by step, with how far the bar moves and
+/// how many pedals and levers change from the chord before
+pub fn print_progression(
+    guitar: &Guitar,
+    steps: &[Step],
+    options: &DisplayOptions,
+) -> Result<(), DisplayError> {
+    for (i, step) in steps.iter().enumerate() {
+        let strings: Vec<String> = step
+            .grip
+            .strings()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let mut name = format!(
+            "{}. {}: {}, bar at {}, strings {}",
+            i + 1,
+            chord_name(&step.chord),
+            position_name(&step.position),
+            step.grip.bar,
+            strings.join(", ")
+        );
+        if let Some(previous) = i.checked_sub(1).map(|j| &steps[j]) {
+            let travel = step.bar_travel(previous);
+            let bar = match travel {
+                0 => "bar stays".to_string(),
+                1.. => format!("bar up {travel}"),
+                _ => format!("bar down {}", travel.unsigned_abs()),
+            };
+            let changes = match step.control_changes(previous) {
+                0 => "no pedal or lever changes".to_string(),
+                1 => "1 pedal or lever change".to_string(),
+                n => format!("{n} pedal and lever changes"),
+            };
+            write!(name, " ({bar}, {changes})").ok();
+        }
+
+        let root = Root::Chord(&step.chord);
+        print_neck_positions(
+            guitar,
+            &step.grip.notes,
+            Some(&name),
+            &step.grip.changes,
+            &root,
+            &|pos| options.label(pos, &root),
+            options,
+        )?;
+    }
+    Ok(())
+}
+
+/// Print which strings move when going from one combination of pedals and
+/// levers to another, and the chords heard before and after
+pub fn print_voice_leading(
+    guitar: &Guitar,
+    from: &[String],
+    to: &[String],
+    bar: Bar,
+    leading: &VoiceLeading,
+    options: &DisplayOptions,
+) {
+    println!("{}, bar at {bar}", guitar.name);
+    println!(" {} \u{2192} {}", position_name(from), position_name(to));
+
+    let mut moves = leading.moves().peekable();
+    if moves.peek().is_none() {
+        println!("  no strings move");
+    }
+    for voice in moves {
+        println!(
+            "  string {}: {} \u{2192} {} ({:+})",
+            voice.string,
+            options.note(&spell_midi(voice.from, &leading.before)),
+            options.note(&spell_midi(voice.to, &leading.after)),
+            voice.semitones()
+        );
+    }
+
+    let before: Vec<u8> = leading.voices.iter().map(|voice| voice.from).collect();
+    let after: Vec<u8> = leading.voices.iter().map(|voice| voice.to).collect();
+    print_chord_matches(from, &before, &leading.before, options);
+    print_chord_matches(to, &after, &leading.after, options);
+
+    // How the root of the chord moves, e.g. up a P4 from I to IV
+    if let (Some(before), Some(after)) = (leading.before.first(), leading.after.first()) {
+        let interval = Interval::between(before.chord.root, after.chord.root);
+        if interval.semitones == 0 {
+            println!("Root stays on {}", after.chord.root);
+        } else {
+            println!(
+                "Root moves up a {} from {} to {}",
+                interval.name(),
+                before.chord.root,
+                after.chord.root
+            );
+        }
     }
 }
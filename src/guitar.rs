@@ -4,90 +4,353 @@
 //! instrument, along with functions to identify notes on the neck based on a
 //! given tuning and copedent (pedal and lever changes). It also includes
 //! functionality to find frets that contain all chord tones for a specified chord.
+//!
+//! Pitches on the neck are worked out as absolute MIDI note numbers so pedal and
+//! lever changes raise or lower a string to the note it really sounds.
 
 use crate::{
-    copedent::{Position, pedal_and_levers},
-    tunings::tuning,
+    copedent::{Copedent, CopedentError, pedal_and_levers},
+    spelling::serialize_pitch,
+    tunings::{AbsolutePitch, Tuning, TuningError},
 };
 use rust_music_theory::{
     chord::Chord,
     note::{Note, Notes, Pitch},
-    scale::Direction,
 };
-use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    ops::RangeInclusive,
+    str::FromStr,
+};
+use strum_macros::Display;
+
+/// Scale length of a typical pedal steel, in inches
+pub const DEFAULT_SCALE_LENGTH: f64 = 24.0;
+
+/// Number of frets marked on a typical steel guitar fretboard
+pub const DEFAULT_FRETS: usize = 24;
 
 /// Representation of a guitar or pedal-steel instrument
+#[derive(Serialize)]
 pub struct Guitar {
     pub name: String,
-    pub tuning: Vec<Pitch>,
+    /// Sounding pitch of each open string, string 1 first
+    pub tuning: Vec<AbsolutePitch>,
+    /// Distance from the nut to the bridge, in inches
+    pub scale_length: f64,
+    /// Number of frets on the fretboard
+    pub frets: usize,
 }
 
 impl Guitar {
-    pub fn new(name: &str, notes: &str) -> Self {
-        Self {
+    /// Create a guitar from a comma-separated tuning, string 1 first. Notes
+    /// may give their octave ("G#4") or be MIDI numbers; see [`Tuning`].
+    pub fn new(name: &str, notes: &str) -> Result<Self, TuningError> {
+        let Tuning { strings } = notes.parse()?;
+        Ok(Self {
             name: name.to_string(),
-            tuning: tuning(notes),
+            tuning: strings,
+            scale_length: DEFAULT_SCALE_LENGTH,
+            frets: DEFAULT_FRETS,
+        })
+    }
+
+    /// Distance from the nut to a fret, in inches
+    pub fn fret_distance(&self, fret: usize) -> f64 {
+        let fret = i32::try_from(fret).unwrap_or(i32::MAX);
+        self.scale_length * (1.0 - 2_f64.powf(-f64::from(fret) / 12.0))
+    }
+
+    /// Check that a range of frets is on the fretboard
+    pub fn fret_range(&self, range: FretRange) -> Result<FretRange, FretRangeError> {
+        if range.end > self.frets {
+            return Err(FretRangeError::PastLastFret {
+                end: range.end,
+                frets: self.frets,
+            });
+        }
+        Ok(range)
+    }
+}
+
+/// Number of inlay dots marking a fret on the fretboard: one at frets 3, 5, 7
+/// and 9, two at the octaves
+pub fn inlay_dots(fret: usize) -> usize {
+    match fret % 12 {
+        0 if fret > 0 => 2,
+        3 | 5 | 7 | 9 => 1,
+        _ => 0,
+    }
+}
+
+/// A range of frets to show, both ends included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FretRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FretRange {
+    /// The first twelve frets, from the open strings to fret 11
+    pub const fn first_twelve() -> Self {
+        Self { start: 0, end: 11 }
+    }
+
+    /// The frets in the range
+    pub fn frets(&self) -> RangeInclusive<usize> {
+        self.start..=self.end
+    }
+
+    /// Whether `fret` is in the range
+    pub fn contains(&self, fret: usize) -> bool {
+        self.frets().contains(&fret)
+    }
+}
+
+impl Default for FretRange {
+    fn default() -> Self {
+        Self::first_twelve()
+    }
+}
+
+impl fmt::Display for FretRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// Errors that can occur while parsing a fret range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FretRangeError {
+    /// The range is not written as "START..END"
+    Syntax(String),
+    /// The range ends before it starts
+    Reversed { start: usize, end: usize },
+    /// The range goes past the last fret of the guitar
+    PastLastFret { end: usize, frets: usize },
+}
+
+impl fmt::Display for FretRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(s) => write!(f, "{s:?} is not a fret range like \"0..12\""),
+            Self::Reversed { start, end } => {
+                write!(f, "fret range {start}..{end} ends before it starts")
+            }
+            Self::PastLastFret { end, frets } => write!(
+                f,
+                "fret {end} is past the end of a fretboard with {frets} frets"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FretRangeError {}
+
+impl FromStr for FretRange {
+    type Err = FretRangeError;
+
+    /// Parse "START..END", e.g. "12..24". Both ends are included. Whether the
+    /// range fits the fretboard is checked with [`Guitar::fret_range`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let syntax = || FretRangeError::Syntax(s.to_string());
+        let (start, end) = s.split_once("..").ok_or_else(syntax)?;
+        let start: usize = start.trim().parse().map_err(|_| syntax())?;
+        let end: usize = end.trim().parse().map_err(|_| syntax())?;
+
+        if start > end {
+            return Err(FretRangeError::Reversed { start, end });
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// Which way the bar is angled across the strings
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slant {
+    /// Square across the strings: every string at the same fret
+    #[strum(serialize = "straight")]
+    Straight,
+    /// Tip of the bar towards the nut: the low strings are stopped at a lower
+    /// fret than the high strings
+    #[strum(serialize = "forward slant")]
+    Forward,
+    /// Tip of the bar towards the bridge: the low strings are stopped at a
+    /// higher fret than the high strings
+    #[strum(serialize = "reverse slant")]
+    Reverse,
+}
+
+/// Where the bar lies across the strings it covers: at `fret` on the highest
+/// string (the lowest string number) and `slant` frets further up the neck on
+/// the lowest string. A negative slant is a forward slant and a positive one a
+/// reverse slant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bar {
+    pub fret: usize,
+    pub slant: isize,
+}
+
+impl Bar {
+    /// A bar square across the strings at `fret`
+    pub const fn straight(fret: usize) -> Self {
+        Self { fret, slant: 0 }
+    }
+
+    /// Which way the bar is angled
+    pub fn kind(&self) -> Slant {
+        match self.slant.signum() {
+            0 => Slant::Straight,
+            -1 => Slant::Forward,
+            _ => Slant::Reverse,
+        }
+    }
+
+    /// The fret under each string when the bar covers `strings`, in the same
+    /// order. The bar is straight, so a string between the outside ones is
+    /// stopped part way along the slant; `None` when that falls between two
+    /// frets (and would sound out of tune). A slanted bar cannot reach the nut,
+    /// so it never leaves a string open.
+    pub fn frets(&self, strings: &[usize]) -> Option<Vec<usize>> {
+        let first = strings.iter().min()?;
+        let span = strings.iter().max()? - first;
+        let fret = isize::try_from(self.fret).ok()?;
+        strings
+            .iter()
+            .map(|string| {
+                if span == 0 {
+                    return Some(self.fret);
+                }
+                let along = isize::try_from(string - first).ok()? * self.slant;
+                let span = isize::try_from(span).ok()?;
+                if along % span != 0 {
+                    return None;
+                }
+                usize::try_from(fret + along / span)
+                    .ok()
+                    .filter(|&fret| fret > 0 || self.slant == 0)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Bar {
+    /// "5" for a straight bar, "5/3" when the low string is at the third fret
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.fret.checked_add_signed(self.slant) {
+            Some(last) if self.slant != 0 => write!(f, "{}/{last}", self.fret),
+            _ => write!(f, "{}", self.fret),
         }
     }
 }
 
 /// Identify notes on the guitar neck for a given position and return their positions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct NeckPositions {
+    #[serde(serialize_with = "serialize_pitch")]
     pub pitch: Pitch,
+    /// Sounding pitch as a MIDI note number
+    pub midi: u8,
     pub note_name: String,
+    /// String index, counting from 0. Serialized counting from 1, like the CLI.
+    #[serde(serialize_with = "serialize_string_number")]
     pub string: usize,
     pub fret: usize,
 }
 
-fn display_as_flats_or_sharps(notes: &[Note]) -> Direction {
-    if notes.iter().any(|n| n.pitch.accidental < 0) {
-        Direction::Descending
-    } else {
-        Direction::Ascending
-    }
+/// Write a string index counting from 1, the way strings are numbered on the CLI
+fn serialize_string_number<S: Serializer>(
+    string: &usize,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    (string + 1).serialize(serializer)
 }
 
-fn populate_neck_pitches(
-    guitar: &Guitar,
-    position: &[Position],
-    direction: Direction,
-) -> Vec<Vec<Pitch>> {
-    let pedal_and_levers = pedal_and_levers(position);
+impl NeckPositions {
+    /// The note with its octave, spelled like `note_name`
+    pub fn absolute_pitch(&self) -> AbsolutePitch {
+        AbsolutePitch {
+            pitch: self.pitch,
+            midi: self.midi,
+        }
+    }
+
+    /// Whether the engaged pedals and levers change the note, i.e. it is not
+    /// what the open string sounds at this fret
+    pub fn is_altered(&self, guitar: &Guitar) -> bool {
+        guitar
+            .tuning
+            .get(self.string)
+            .is_some_and(|open| usize::from(open.midi) + self.fret != usize::from(self.midi))
+    }
+}
 
+/// Work out the sounding MIDI note of every string and fret, up to the last
+/// fret of the guitar, with each string moved by its entry in `changes` (see
+/// [`pedal_and_levers`]). Frets that would sound outside the MIDI range
+/// (0 to 127) are `None`.
+pub(crate) fn populate_neck_pitches(guitar: &Guitar, changes: &[i8]) -> Vec<Vec<Option<u8>>> {
     let mut neck = Vec::new();
-    for i in 0..guitar.tuning.len() {
-        let row = (0..12)
+    for (open, change) in guitar.tuning.iter().zip(changes) {
+        let row = (0..=guitar.frets)
             .map(|j| {
-                Pitch::from_u8_with_direction(
-                    guitar.tuning[i].into_u8()
-                        + pedal_and_levers.copedent_change[i]
-                        + u8::try_from(j).unwrap_or(0),
-                    direction,
-                )
+                let j = i16::try_from(j).unwrap_or(i16::MAX);
+                let midi = i16::from(open.midi) + i16::from(*change) + j;
+                u8::try_from(midi).ok().filter(|&midi| midi <= 127)
             })
             .collect::<Vec<_>>();
         neck.push(row);
     }
-
     neck
 }
 
-/// Identify notes on the guitar neck for a given position and return their positions
+/// Identify notes on the guitar neck for a given position and return their
+/// positions, spelled the way `notes` spells them (an E# stays an E#). Fails
+/// if the position cannot be played on the copedent.
+///
+/// With a `bar` laid across every string, only the notes under it are found,
+/// each string at the fret the bar stops it (see [`Bar::frets`]), so a forward
+/// or reverse slant is followed string by string. A slant that stops a string
+/// between two frets finds no notes at all.
 pub fn identify_notes_on_neck(
     guitar: &Guitar,
-    position: &[Position],
+    copedent: &Copedent,
+    position: &[String],
     notes: &[Note],
-) -> Vec<NeckPositions> {
-    let direction = display_as_flats_or_sharps(notes);
-    let neck = populate_neck_pitches(guitar, position, direction);
+    bar: Option<Bar>,
+) -> Result<Vec<NeckPositions>, CopedentError> {
+    let changes = pedal_and_levers(copedent, position, guitar.tuning.len())?.copedent_change;
+    let neck_positions = notes_on_neck(guitar, &changes, notes);
+    let Some(bar) = bar else {
+        return Ok(neck_positions);
+    };
+
+    let strings: Vec<usize> = (1..=guitar.tuning.len()).collect();
+    let frets = bar.frets(&strings).unwrap_or_default();
+    Ok(neck_positions
+        .into_iter()
+        .filter(|note| frets.get(note.string) == Some(&note.fret))
+        .collect())
+}
+
+/// Identify notes on the guitar neck with each string moved by its entry in
+/// `changes`, for when the changes of a position are already worked out.
+/// Frets that would sound outside the MIDI range are left out.
+pub fn notes_on_neck(guitar: &Guitar, changes: &[i8], notes: &[Note]) -> Vec<NeckPositions> {
+    let neck = populate_neck_pitches(guitar, changes);
 
     let mut neck_positions = Vec::new();
     for (i, row) in neck.iter().enumerate() {
-        for (j, pitch) in row.iter().enumerate() {
-            if notes.iter().any(|note| note.pitch == *pitch) {
+        for (j, midi) in row.iter().enumerate() {
+            let Some(midi) = midi else { continue };
+            // Match on the sounding pitch class and keep the spelling of the note asked for
+            if let Some(note) = notes.iter().find(|note| note.pitch.into_u8() == midi % 12) {
+                let pitch = note.pitch;
                 neck_positions.push(NeckPositions {
-                    pitch: *pitch,
+                    pitch,
+                    midi: *midi,
                     note_name: format!("{pitch}"),
                     string: i,
                     fret: j,
@@ -98,17 +361,14 @@ pub fn identify_notes_on_neck(
     neck_positions
 }
 
-/// Find frets that contain all chord tones and return their positions
+/// Find frets that contain all chord tones and return their positions, lowest
+/// fret first
 pub fn frets_with_all_chord_tones(
     neck_positions: &[NeckPositions],
     chord: &Chord,
 ) -> Vec<NeckPositions> {
-    let chord_pitches: HashSet<_> = chord
-        .notes()
-        .iter()
-        .map(|n| format!("{}", n.pitch))
-        .collect();
-    let mut fret_map: HashMap<usize, Vec<&NeckPositions>> = HashMap::new();
+    let chord_pitches: HashSet<_> = chord.notes().iter().map(|n| n.pitch.into_u8()).collect();
+    let mut fret_map: BTreeMap<usize, Vec<&NeckPositions>> = BTreeMap::new();
 
     // Group NeckPositions by fret
     for pos in neck_positions {
@@ -117,10 +377,9 @@ pub fn frets_with_all_chord_tones(
 
     // Collect NeckPositions for frets that have all chord tones
     fret_map
-        .into_iter()
-        .filter_map(|(_fret, positions)| {
-            let pitches_on_fret: HashSet<_> =
-                positions.iter().map(|p| format!("{}", p.pitch)).collect();
+        .into_values()
+        .filter_map(|positions| {
+            let pitches_on_fret: HashSet<_> = positions.iter().map(|p| p.pitch.into_u8()).collect();
             if chord_pitches.is_subset(&pitches_on_fret) {
                 Some(
                     positions
@@ -139,43 +398,303 @@ pub fn frets_with_all_chord_tones(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        spelling::scale_notes,
+        test_support::e9,
+        tunings::{midi_tuning, tuning},
+    };
+    use rust_music_theory::{note::NoteLetter, scale::Scale};
 
     #[test]
     fn test_guitar_creation() {
         let notes = "E, C#, A, F#, E, C#, A, F#";
-        let guitar = Guitar::new("A6th lap steel", notes);
+        let guitar = Guitar::new("A6th lap steel", notes).unwrap();
         assert_eq!(guitar.name, "A6th lap steel");
-        assert_eq!(guitar.tuning, tuning(notes));
         assert_eq!(guitar.tuning.len(), 8);
+        let pitches: Vec<_> = guitar.tuning.iter().map(|s| s.pitch).collect();
+        let midi: Vec<_> = guitar.tuning.iter().map(|s| s.midi).collect();
+        assert_eq!(pitches, tuning(notes).unwrap());
+        assert_eq!(midi, midi_tuning(&tuning(notes).unwrap()));
+    }
+
+    #[test]
+    fn test_guitar_keeps_octaves() {
+        let guitar = Guitar::new("E9", "F#4, D#4, G#4, E4, B3, G#3, F#3, E3, D3, B2").unwrap();
+        assert_eq!(guitar.tuning[9].midi, 47);
+        assert_eq!(format!("{}", guitar.tuning[2]), "G#4");
+    }
+
+    #[test]
+    fn test_guitar_rejects_unknown_notes() {
+        let result = Guitar::new("E13", "P Don Helms");
+        assert!(matches!(
+            result,
+            Err(TuningError::InvalidNote { index: 0, .. })
+        ));
     }
 
     #[test]
     fn test_identify_notes_on_neck() {
-        let guitar = Guitar::new("Test Guitar", "E");
-        let position = vec![Position::Open];
+        let guitar = Guitar::new("Test Guitar", "E").unwrap();
+        let position = vec![];
         let chord = Chord::from_regex("E major").unwrap();
+        let frets = FretRange::first_twelve();
 
-        let neck_positions = identify_notes_on_neck(&guitar, &position, &chord.notes());
+        let neck_positions: Vec<_> = identify_notes_on_neck(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            &chord.notes(),
+            None,
+        )
+        .unwrap()
+        .into_iter()
+        .filter(|p| frets.contains(p.fret))
+        .collect();
 
         assert_eq!(neck_positions.len(), 3);
         assert_eq!(neck_positions[0].string, 0);
         assert_eq!(neck_positions[0].fret, 0);
         assert_eq!(neck_positions[0].note_name, "E");
+        assert_eq!(neck_positions[0].midi, 64);
         assert_eq!(neck_positions[1].string, 0);
         assert_eq!(neck_positions[1].fret, 4);
         assert_eq!(neck_positions[1].note_name, "G#");
         assert_eq!(neck_positions[2].string, 0);
         assert_eq!(neck_positions[2].fret, 7);
         assert_eq!(neck_positions[2].note_name, "B");
+        assert_eq!(neck_positions[2].midi, 71);
+    }
+
+    #[test]
+    fn test_identify_notes_on_neck_covers_every_fret() {
+        let guitar = Guitar::new("Test Guitar", "E").unwrap();
+        let chord = Chord::from_regex("E major").unwrap();
+
+        let neck_positions =
+            identify_notes_on_neck(&guitar, &Copedent::default(), &[], &chord.notes(), None)
+                .unwrap();
+
+        let frets: Vec<usize> = neck_positions.iter().map(|p| p.fret).collect();
+        assert_eq!(frets, vec![0, 4, 7, 12, 16, 19, 24]);
+        assert_eq!(neck_positions[6].midi, 88);
+    }
+
+    #[test]
+    fn test_bar_frets() {
+        let straight = Bar::straight(5);
+        assert_eq!(straight.kind(), Slant::Straight);
+        assert_eq!(straight.frets(&[3, 4, 5]), Some(vec![5, 5, 5]));
+
+        let forward = Bar { fret: 5, slant: -2 };
+        assert_eq!(forward.kind(), Slant::Forward);
+        assert_eq!(forward.frets(&[3, 4, 5]), Some(vec![5, 4, 3]));
+        // The middle string would be stopped between two frets
+        assert_eq!(Bar { fret: 5, slant: 1 }.frets(&[3, 4, 5]), None);
+        // Unless it is skipped
+        assert_eq!(Bar { fret: 5, slant: 1 }.frets(&[3, 5]), Some(vec![5, 6]));
+        assert_eq!(Bar { fret: 1, slant: -2 }.frets(&[1, 2]), None);
+        assert_eq!(Bar { fret: 2, slant: -2 }.frets(&[1, 2]), None);
+        assert_eq!(Bar::straight(0).frets(&[1, 2]), Some(vec![0, 0]));
+
+        assert_eq!(forward.to_string(), "5/3");
+        assert_eq!(straight.to_string(), "5");
+    }
+
+    #[test]
+    fn test_fret_range() {
+        assert_eq!(
+            "12..24".parse::<FretRange>(),
+            Ok(FretRange { start: 12, end: 24 })
+        );
+        assert_eq!("3 .. 7".parse::<FretRange>().unwrap().to_string(), "3..7");
+        assert!(matches!(
+            "12-24".parse::<FretRange>(),
+            Err(FretRangeError::Syntax(_))
+        ));
+        assert!(matches!(
+            "7..5".parse::<FretRange>(),
+            Err(FretRangeError::Reversed { start: 7, end: 5 })
+        ));
+    }
+
+    #[test]
+    fn test_guitar_fret_range() {
+        let mut guitar = Guitar::new("E9", "E").unwrap();
+        let range = "0..25".parse::<FretRange>().unwrap();
+        assert_eq!(
+            guitar.fret_range(range),
+            Err(FretRangeError::PastLastFret { end: 25, frets: 24 })
+        );
+        guitar.frets = 12;
+        assert_eq!(
+            guitar.fret_range(FretRange::first_twelve()),
+            Ok(FretRange::first_twelve())
+        );
+        let range = "12..13".parse::<FretRange>().unwrap();
+        assert_eq!(
+            guitar.fret_range(range).unwrap_err().to_string(),
+            "fret 13 is past the end of a fretboard with 12 frets"
+        );
+    }
+
+    #[test]
+    fn test_fret_distance() {
+        let guitar = Guitar::new("E9", "E").unwrap();
+        assert_eq!(guitar.fret_distance(0), 0.0);
+        assert!((guitar.fret_distance(12) - 12.0).abs() < 1e-9);
+        assert!((guitar.fret_distance(24) - 18.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_identify_notes_on_neck_lowers_to_sounding_note() {
+        let guitar = e9();
+        let position = vec!["RKL".to_string()];
+        let chord = Chord::from_regex("F# major").unwrap();
+
+        let neck_positions = identify_notes_on_neck(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            &chord.notes(),
+            None,
+        )
+        .unwrap();
+
+        // RKL lowers the sixth string G#3 a whole step to F#3
+        let lowered = neck_positions
+            .iter()
+            .find(|p| p.string == 5 && p.fret == 0)
+            .unwrap();
+        assert_eq!(lowered.note_name, "F#");
+        assert_eq!(lowered.midi, 54);
+    }
+
+    #[test]
+    fn test_notes_on_neck_skip_frets_outside_midi() {
+        // C-1 is MIDI 0; lowered a semitone the open string has no MIDI note
+        let guitar = Guitar::new("Low", "0").unwrap();
+        let notes = [Note::new(Pitch::new(NoteLetter::C, 0), 0)];
+        let frets = |changes: &[i8]| -> Vec<usize> {
+            notes_on_neck(&guitar, changes, &notes)
+                .iter()
+                .map(|note| note.fret)
+                .collect()
+        };
+        assert_eq!(frets(&[0]), [0, 12, 24]);
+        assert_eq!(frets(&[-1]), [1, 13]);
+    }
+
+    #[test]
+    fn test_identify_notes_on_neck_under_a_slant() {
+        let guitar = Guitar::new("Test Guitar", "E, C#, A").unwrap();
+        let g_major = Chord::from_regex("G major").unwrap().notes();
+        let under = |bar: Bar| -> Vec<(usize, usize, String)> {
+            identify_notes_on_neck(&guitar, &Copedent::open(), &[], &g_major, Some(bar))
+                .unwrap()
+                .iter()
+                .map(|note| (note.string, note.fret, note.note_name.clone()))
+                .collect()
+        };
+
+        // A forward slant from fret 7 on string 1 to fret 5 on string 3: B, G and D
+        assert_eq!(
+            under(Bar { fret: 7, slant: -2 }),
+            [
+                (0, 7, "B".to_string()),
+                (1, 6, "G".to_string()),
+                (2, 5, "D".to_string())
+            ]
+        );
+        // A reverse slant from fret 3 to fret 5 sounds G, F and D; F is no chord tone
+        assert_eq!(
+            under(Bar { fret: 3, slant: 2 }),
+            [(0, 3, "G".to_string()), (2, 5, "D".to_string())]
+        );
+        // Straight at fret 7 only the B on string 1 is a chord tone
+        assert_eq!(under(Bar::straight(7)), [(0, 7, "B".to_string())]);
+        // Slanting one fret across three strings stops string 2 between frets
+        assert!(under(Bar { fret: 7, slant: -1 }).is_empty());
+    }
+
+    #[test]
+    fn test_is_altered() {
+        let guitar = e9();
+        let position = vec!["A".to_string()];
+        let chord = Chord::from_regex("A major").unwrap();
+
+        let neck_positions = identify_notes_on_neck(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            &chord.notes(),
+            None,
+        )
+        .unwrap();
+
+        // The A pedal raises strings 5 and 10; the open E on string 4 is untouched
+        let altered: HashSet<usize> = neck_positions
+            .iter()
+            .filter(|p| p.is_altered(&guitar))
+            .map(|p| p.string)
+            .collect();
+        assert_eq!(altered, HashSet::from([4, 9]));
+        assert!(neck_positions.iter().any(|p| p.string == 3 && p.fret == 0));
+    }
+
+    #[test]
+    fn test_identify_notes_on_neck_keeps_spelling() {
+        let guitar = Guitar::new("Test Guitar", "F").unwrap();
+        let notes: Vec<Note> = tuning("E#, Gb")
+            .unwrap()
+            .into_iter()
+            .map(|pitch| Note::new(pitch, 0))
+            .collect();
+
+        let neck_positions =
+            identify_notes_on_neck(&guitar, &Copedent::default(), &[], &notes, None).unwrap();
+
+        assert_eq!(neck_positions[0].note_name, "E#");
+        assert_eq!(neck_positions[1].note_name, "Gb");
+        assert_eq!(format!("{}", neck_positions[0].absolute_pitch()), "E#4");
+    }
+
+    #[test]
+    fn test_identify_notes_on_neck_spells_from_the_key() {
+        let guitar = Guitar::new("Test Guitar", "F").unwrap();
+        let scale = Scale::from_regex("C# major").unwrap();
+
+        let neck_positions = identify_notes_on_neck(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            &scale_notes(&scale),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(neck_positions[0].fret, 0);
+        assert_eq!(neck_positions[0].note_name, "E#");
     }
 
     #[test]
     fn test_frets_with_all_chord_tones() {
-        let guitar = Guitar::new("Test Guitar", "E, G#, B");
-        let position = vec![Position::Open];
+        let guitar = Guitar::new("Test Guitar", "E, G#, B").unwrap();
+        let position = vec![];
         let chord = Chord::from_regex("E major").unwrap();
+        let first_twelve = FretRange::first_twelve();
 
-        let neck_positions = identify_notes_on_neck(&guitar, &position, &chord.notes());
+        let neck_positions: Vec<_> = identify_notes_on_neck(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            &chord.notes(),
+            None,
+        )
+        .unwrap()
+        .into_iter()
+        .filter(|p| first_twelve.contains(p.fret))
+        .collect();
         let frets = frets_with_all_chord_tones(&neck_positions, &chord);
 
         assert_eq!(frets.len(), 3);
@@ -189,4 +708,36 @@ mod tests {
         assert_eq!(frets[2].fret, 0);
         assert_eq!(frets[2].note_name, "B");
     }
+
+    #[test]
+    fn test_frets_with_all_chord_tones_in_fret_order() {
+        let guitar = Guitar::new("Test Guitar", "E, G#, B").unwrap();
+        let chord = Chord::from_regex("E major").unwrap();
+
+        let neck_positions =
+            identify_notes_on_neck(&guitar, &Copedent::default(), &[], &chord.notes(), None)
+                .unwrap();
+        let frets: Vec<usize> = frets_with_all_chord_tones(&neck_positions, &chord)
+            .iter()
+            .map(|p| p.fret)
+            .collect();
+
+        assert_eq!(frets, [0, 0, 0, 12, 12, 12, 24, 24, 24]);
+    }
+
+    #[test]
+    fn test_identify_notes_on_neck_rejects_unplayable_position() {
+        let guitar = e9();
+        let position = vec!["LKL".to_string(), "LKR".to_string()];
+        let chord = Chord::from_regex("E major").unwrap();
+
+        let result = identify_notes_on_neck(
+            &guitar,
+            &Copedent::default(),
+            &position,
+            &chord.notes(),
+            None,
+        );
+        assert!(matches!(result, Err(CopedentError::Unplayable { .. })));
+    }
 }
@@ -0,0 +1,204 @@
+//! HTML reports — every combination of pedals and levers on one page.
+//!
+//! The page is a single file with its styles, script and drawings inline, so it
+//! opens offline and can be passed around as it is. The copedent is charted at
+//! the top; below it there is a tab for each combination, and the first tab
+//! shows every neck diagram as a grid. Resting the pointer on a note shows its
+//! degree and interval from the root.
+
+use crate::{
+    copedent::{Copedent, position_name},
+    display::{DisplayOptions, Root},
+    guitar::NeckPositions,
+    report::NeckReport,
+    svg::{escape, neck_drawing},
+};
+use std::fmt::Write;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table.copedent { border-collapse: collapse; margin-bottom: 2em; }
+table.copedent th, table.copedent td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: center; }
+table.copedent td[title] { cursor: help; }
+nav { display: flex; flex-wrap: wrap; gap: 0.3em; margin-bottom: 1em; }
+nav button { border: 1px solid #aaa; background: #f5efe0; padding: 0.3em 0.8em; cursor: pointer; }
+nav button.active { background: #222; color: #fff; }
+.panels { display: grid; grid-template-columns: repeat(auto-fill, minmax(540px, 1fr)); gap: 1em; }
+.panels figure { margin: 0; }
+.panels svg { max-width: 100%; height: auto; }
+";
+
+const SCRIPT: &str = "
+const buttons = document.querySelectorAll('nav button');
+buttons.forEach((button) => button.addEventListener('click', () => {
+  buttons.forEach((other) => other.classList.toggle('active', other === button));
+  document.querySelectorAll('.panels figure').forEach((panel) => {
+    panel.hidden = button.dataset.tab !== 'all' && panel.dataset.tab !== button.dataset.tab;
+  });
+}));
+";
+
+/// Chart the copedent: one row per string with its open note and one column
+/// per pedal and lever. The label of a change, e.g. "B→C#", shows on hover.
+fn copedent_table(
+    html: &mut String,
+    report: &NeckReport,
+    copedent: &Copedent,
+    options: &DisplayOptions,
+) {
+    html.push_str("<table class=\"copedent\">\n<tr><th>String</th><th>Open</th>");
+    for control in &copedent.controls {
+        write!(html, "<th>{}</th>", escape(&control.name)).ok();
+    }
+    html.push_str("</tr>\n");
+
+    for (i, open) in report.guitar.tuning.iter().enumerate() {
+        write!(
+            html,
+            "<tr><td>{}</td><td>{}</td>",
+            i + 1,
+            options.note(open)
+        )
+        .ok();
+        for control in &copedent.controls {
+            match control.change(i + 1) {
+                Some(change) => {
+                    let mut text = format!("{:+}", change.semitone_change);
+                    if let Some(half_stop) = change.half_stop {
+                        write!(text, " (½ {half_stop:+})").ok();
+                    }
+                    match &change.label {
+                        Some(label) => {
+                            write!(html, "<td title=\"{}\">{text}</td>", escape(label)).ok();
+                        }
+                        None => {
+                            write!(html, "<td>{text}</td>").ok();
+                        }
+                    }
+                }
+                None => html.push_str("<td></td>"),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    for split in &copedent.overrides {
+        writeln!(
+            html,
+            "<p>{}: string {} {:+}</p>",
+            escape(&position_name(&split.controls)),
+            split.string,
+            split.semitone_change
+        )
+        .ok();
+    }
+}
+
+/// Write the page for the neck diagrams of `report`. `title` names what they
+/// show, e.g. "E Major Triad", and notes are labelled counting from `root`.
+pub fn html_report(
+    report: &NeckReport,
+    copedent: &Copedent,
+    title: &str,
+    root: &Root,
+    options: &DisplayOptions,
+) -> String {
+    let heading = if report.guitar.name.is_empty() {
+        escape(title)
+    } else {
+        escape(&format!("{}: {title}", report.guitar.name))
+    };
+    let label = |note: &NeckPositions| options.label(note, root);
+    let hover = |note: &NeckPositions| {
+        let interval = root.interval(note.pitch);
+        format!(
+            "{} on string {}, fret {}: {} ({})",
+            options.note(&note.absolute_pitch()),
+            note.string + 1,
+            note.fret,
+            interval.degree_name(),
+            interval.name()
+        )
+    };
+
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{heading}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{heading}</h1>"
+    )
+    .ok();
+    if !copedent.controls.is_empty() {
+        if !copedent.name.is_empty() {
+            writeln!(html, "<h2>{} copedent</h2>", escape(&copedent.name)).ok();
+        }
+        copedent_table(&mut html, report, copedent, options);
+    }
+
+    html.push_str("<nav>\n<button class=\"active\" data-tab=\"all\">All</button>\n");
+    for (i, diagram) in report.diagrams.iter().enumerate() {
+        writeln!(
+            html,
+            "<button data-tab=\"{i}\">{}</button>",
+            escape(&diagram.name)
+        )
+        .ok();
+    }
+    html.push_str("</nav>\n<div class=\"panels\">\n");
+    for (i, diagram) in report.diagrams.iter().enumerate() {
+        writeln!(
+            html,
+            "<figure data-tab=\"{i}\">\n{}</figure>",
+            neck_drawing(report.guitar, diagram, report.frets, &label, &hover)
+        )
+        .ok();
+    }
+    writeln!(html, "</div>\n<script>{SCRIPT}</script>\n</body>\n</html>").ok();
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{guitar::FretRange, report::NeckDiagram, spelling::chord_notes, test_support::e9};
+    use rust_music_theory::chord::Chord;
+
+    #[test]
+    fn test_html_report() {
+        let guitar = e9();
+        let copedent = Copedent::default();
+        let chord = Chord::from_regex("E major").unwrap();
+        let frets = FretRange::first_twelve();
+        let diagrams = [vec![], vec!["A".to_string(), "B".to_string()]]
+            .iter()
+            .map(|position| {
+                NeckDiagram::new(&guitar, &copedent, position, &chord_notes(&chord), frets).unwrap()
+            })
+            .collect();
+        let report = NeckReport {
+            guitar: &guitar,
+            frets,
+            diagrams,
+        };
+        let html = html_report(
+            &report,
+            &copedent,
+            "E Major Triad",
+            &Root::Chord(&chord),
+            &DisplayOptions::default(),
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>E9: E Major Triad</h1>"));
+        // A tab for all the diagrams and one for each combination
+        assert_eq!(html.matches("<button").count(), 3);
+        assert!(html.contains("<button data-tab=\"1\">A &amp; B</button>"));
+        // The copedent chart, with the label of a change on hover
+        assert!(html.contains("<td title=\"B→C#\">+2</td>"));
+        // Every note tells its degree and interval on hover
+        assert!(html.contains("<title>G# on string 3, fret 0: 3 (M3)</title>"));
+        // Nothing is loaded from elsewhere
+        assert!(!html.contains("src="));
+        assert!(!html.contains("<link"));
+    }
+}
@@ -2,7 +2,17 @@
 //!
 //! Expose modules and a small, convenient public API for the CLI and tests.
 
+pub mod chords;
 pub mod copedent;
 pub mod display;
 pub mod guitar;
+pub mod html;
+pub mod presets;
+pub mod progression;
+pub mod report;
+pub mod spelling;
+pub mod svg;
+#[cfg(test)]
+mod test_support;
 pub mod tunings;
+pub mod voice_leading;
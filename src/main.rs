@@ -12,20 +12,60 @@
 //!   cargo run -- scale --tuning-name "E9" --tuning "F#, D#, G#, E, B, G#, F#, E, D, B" --scale "E major"
 //!   cargo run -- chord --tuning-name "E9" --tuning "F#, D#, G#, E, B, G#, F#, E, D, B" --chord "E major"
 //!   cargo run -- notes --tuning-name "E9" --tuning "F#, D#, G#, E, B, G#, F#, E, D, B" --notes "E"
+//!   cargo run -- copedent --copedent copedents/e9.toml
+//!   cargo run -- chord --preset E9 --copedent emmons --chord "A major"
+//!   cargo run -- list tunings
+//!   cargo run -- scale --preset E9 --scale "E major" --positions "Open, A+B"
+//!   cargo run -- tuning --notes "F#4, D#4, G#4, E4, B3, G#3, F#3, E3, D3, B2" --octaves
+//!   cargo run -- scale --preset E9 --scale "E major" --frets 12..24
+//!   cargo run -- chord --preset E9 --chord "E dominant ninth" --label degrees
+//!   cargo run -- grips --preset E9 --chord "A major" --positions "A+B" --label degrees
+//!   cargo run -- progression --preset E9 --chords "1 4 5 1" --key E
+//!   cargo run -- identify --preset E9 --fret 8 --strings 3,4,5 --positions "Open, A+B"
+//!   cargo run -- grips --preset A6 --chord "D major" --slant 2 --diagrams
+//!   cargo run -- chord --preset E9 --chord "E major" --positions "Open" --format json
+//!   cargo run -- scale --preset E9 --scale "E major" --format svg > e-major.svg
+//!   cargo run -- chord --preset E9 --chord "A major" --format svg --out-dir a-major
+//!   cargo run -- chord --preset E9 --chord "E major" --format html > e-major.html
+//!   cargo run -- chord --preset E9 --chord "E dominant seventh" --positions "A+B" --color always
+//!   cargo run -- voice-leading --preset E9 --fret 3 --strings 3,4,5,6 --to A+B
+//!
+//! Exit codes: 0 on success, 1 when a tuning, note list, scale, chord or set of
+//! positions cannot be understood, 2 for command line usage errors, 3 when a
+//! copedent cannot be loaded or does not fit the guitar and 4 when a drawing
+//! cannot be written.
 //!
 //! The CLI (clap) is defined here; functionality is implemented in the
-//! library modules: copedent, display, guitar, and tunings.
+//! library modules: chords, copedent, display, guitar, html, presets,
+//! progression, report, spelling, svg, tunings and voice_leading.
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use pedal_steel::{
-    copedent::{Position, possible_positions},
+    chords::{CHORD_TYPES, IdentifyError, chord_name, find_grips, identify_chords, parse_chord},
+    copedent::{CombinationLimits, Copedent, parse_positions, possible_positions},
     display::{
-        print_chord, print_chord_on_pedal_steel, print_copedent, print_notes_on_neck, print_scale,
-        print_tuning,
+        ColorChoice, DisplayError, DisplayOptions, Format, Label, Root, print_chord,
+        print_chord_matches, print_chord_on_pedal_steel, print_copedent, print_grip_diagram,
+        print_grips, print_json, print_notes_on_neck, print_progression, print_scale, print_tuning,
+        print_voice_leading,
     },
-    guitar::Guitar,
+    guitar::{Bar, FretRange, Guitar, NeckPositions},
+    html::html_report,
+    presets::{self, TuningPreset},
+    progression::{ProgressionError, parse_progression, plan_progression},
+    report::{NeckDiagram, NeckReport},
+    spelling::{chord_notes, scale_notes},
+    svg::{self, neck_sheet},
+    tunings::tuning as parse_tuning,
+    voice_leading::voice_leading,
+};
+use rust_music_theory::{note::Note, scale::Scale};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    slice,
 };
-use rust_music_theory::{chord::Chord, scale::Scale};
 
 // Constant is taken from rust-music-theory crate
 // https://github.com/ozankasikci/rust-music-theory/blob/src/bin/rustmt.rs
@@ -46,40 +86,80 @@ const AVAILABLE_SCALES: [&str; 14] = [
     "Whole Tone",
 ];
 
-// Constant is taken from rust-music-theory crate
-// https://github.com/ozankasikci/rust-music-theory/blob/src/bin/rustmt.rs
-const AVAILABLE_CHORDS: [&str; 22] = [
-    "Major Triad",
-    "Minor Triad",
-    "Suspended2 Triad",
-    "Suspended4 Triad",
-    "Augmented Triad",
-    "Diminished Triad",
-    "Major Seventh",
-    "Minor Seventh",
-    "Augmented Seventh",
-    "Augmented Major Seventh",
-    "Diminished Seventh",
-    "Half Diminished Seventh",
-    "Minor Major Seventh",
-    "Dominant Seventh",
-    "Dominant Ninth",
-    "Major Ninth",
-    "Dominant Eleventh",
-    "Major Eleventh",
-    "Minor Eleventh",
-    "Dominant Thirteenth",
-    "Major Thirteenth",
-    "Minor Thirteenth",
-];
-
 #[derive(Parser)]
 #[command(author, version, about)]
 struct Cli {
+    /// Copedent to use: a built-in copedent (see `list copedents`) or a TOML/JSON file
+    #[arg(long, global = true)]
+    copedent: Option<String>,
+
+    /// Show the octave of every note, e.g. "G#4"
+    #[arg(long, global = true)]
+    octaves: bool,
+
+    /// Colour neck diagrams: auto (only on a terminal), always or never
+    #[arg(long, global = true, default_value_t = ColorChoice::default())]
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Options selecting the tuning of the guitar
+#[derive(Args)]
+struct TuningArgs {
+    /// Name shown above each neck diagram
+    #[arg(long, required_unless_present = "preset")]
+    tuning_name: Option<String>,
+    /// Comma separated notes, string 1 first
+    #[arg(long, required_unless_present = "preset", conflicts_with = "preset")]
+    tuning: Option<String>,
+    /// Built-in tuning, e.g. "E9" (see `list tunings`)
+    #[arg(long)]
+    preset: Option<String>,
+    /// Number of frets on the fretboard [default: the preset's, or 24]
+    #[arg(long)]
+    fret_count: Option<usize>,
+}
+
+/// Options selecting the pedal and lever combinations to show
+#[derive(Args)]
+struct PositionArgs {
+    /// Show only the Open position, as on a lap steel without pedals
+    #[arg(long)]
+    lap_steel: bool,
+    /// Most pedals and levers engaged at once
+    #[arg(long, default_value_t = CombinationLimits::default().max_controls)]
+    max_controls: usize,
+    /// Only show these combinations, e.g. "Open, A+B, LKR"
+    #[arg(long, conflicts_with = "lap_steel")]
+    positions: Option<String>,
+}
+
+/// Options controlling the neck diagrams
+#[derive(Args)]
+struct NeckArgs {
+    /// Frets to show, e.g. "12..24" (both ends included, up to the last fret)
+    #[arg(long, default_value_t = FretRange::default())]
+    frets: FretRange,
+    /// What to write for each note: notes, degrees, intervals or nashville,
+    /// counted from the scale or chord root (or the first of the notes)
+    #[arg(long, default_value_t = Label::default())]
+    label: Label,
+}
+
+/// Options choosing how neck diagrams are written
+#[derive(Args)]
+struct OutputArgs {
+    /// Output format: text, json, svg or html
+    #[arg(long, default_value_t = Format::default())]
+    format: Format,
+    /// With --format svg, write one drawing per combination into this
+    /// directory instead of a single sheet on stdout
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// List available items
@@ -90,47 +170,147 @@ enum Commands {
 
     /// Print a tuning (comma separated notes, e.g. "F#, D#, G#, E, B, G#, F#, E, D, B")
     Tuning {
+        #[arg(long, required_unless_present = "preset", conflicts_with = "preset")]
+        notes: Option<String>,
+        /// Built-in tuning, e.g. "E9" (see `list tunings`)
         #[arg(long)]
-        notes: String,
+        preset: Option<String>,
+        /// Output format: text or json (svg and html are only for neck diagrams)
+        #[arg(long, default_value_t = Format::default())]
+        format: Format,
     },
 
-    /// Print copedent chart (uses internal copedent definitions)
-    Copedent,
+    /// Print copedent chart (uses --copedent, the preset's copedent or the built-in E9 copedent)
+    Copedent {
+        /// Tuning the copedent is for; the chart then shows one row per string
+        #[arg(long, conflicts_with = "preset")]
+        tuning: Option<String>,
+        /// Built-in tuning the copedent is for, e.g. "E9" (see `list tunings`)
+        #[arg(long)]
+        preset: Option<String>,
+        /// Output format: text or json (svg and html are only for neck diagrams)
+        #[arg(long, default_value_t = Format::default())]
+        format: Format,
+    },
 
     /// Show a scale on neck for given tuning
     Scale {
-        #[arg(long)]
-        tuning_name: String,
-        #[arg(long)]
-        tuning: String,
+        #[command(flatten)]
+        tuning: TuningArgs,
         #[arg(long)]
         scale: String,
-        #[arg(long)]
-        lap_steel: bool,
+        #[command(flatten)]
+        positions: PositionArgs,
+        #[command(flatten)]
+        neck: NeckArgs,
+        #[command(flatten)]
+        output: OutputArgs,
     },
 
     /// Show chord positions for given tuning
     Chord {
+        #[command(flatten)]
+        tuning: TuningArgs,
         #[arg(long)]
-        tuning_name: String,
-        #[arg(long)]
-        tuning: String,
+        chord: String,
+        #[command(flatten)]
+        positions: PositionArgs,
+        #[command(flatten)]
+        neck: NeckArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Find playable grips for a chord: 2 to 4 neighbouring strings at one fret
+    Grips {
+        #[command(flatten)]
+        tuning: TuningArgs,
         #[arg(long)]
         chord: String,
+        /// Most grips shown for each combination
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// Also try slanting the bar across up to this many frets
+        #[arg(long, default_value_t = 0)]
+        slant: usize,
+        /// Draw each grip on the neck
         #[arg(long)]
-        lap_steel: bool,
+        diagrams: bool,
+        #[command(flatten)]
+        positions: PositionArgs,
+        #[command(flatten)]
+        neck: NeckArgs,
     },
 
-    /// Show notes on neck for given tuning
-    Notes {
+    /// Plan how to play a chord progression with little bar and pedal movement
+    Progression {
+        #[command(flatten)]
+        tuning: TuningArgs,
+        /// Chord names separated by commas, e.g. "A major, D major", or with
+        /// --key Nashville numbers, e.g. "1 4 5 1"
         #[arg(long)]
-        tuning_name: String,
+        chords: String,
+        /// Key the Nashville numbers count from, e.g. "E"
         #[arg(long)]
-        tuning: String,
+        key: Option<String>,
+        #[command(flatten)]
+        positions: PositionArgs,
+        #[command(flatten)]
+        neck: NeckArgs,
+    },
+
+    /// Name the chords some strings make at a fret in each combination
+    Identify {
+        #[command(flatten)]
+        tuning: TuningArgs,
+        /// Fret the bar is at on the highest string picked
         #[arg(long)]
-        notes: String,
+        fret: usize,
+        /// Frets the bar slants across to the lowest string picked: negative
+        /// for a forward slant, positive for a reverse slant
+        #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+        slant: isize,
+        /// Strings to pick, counting from 1, e.g. "3,4,5"
+        #[arg(long, value_delimiter = ',', required = true)]
+        strings: Vec<usize>,
+        #[command(flatten)]
+        positions: PositionArgs,
+    },
+
+    /// Show which strings move between two combinations and the chords they make
+    VoiceLeading {
+        #[command(flatten)]
+        tuning: TuningArgs,
+        /// Fret the bar is at on the highest string picked
         #[arg(long)]
-        lap_steel: bool,
+        fret: usize,
+        /// Frets the bar slants across to the lowest string picked: negative
+        /// for a forward slant, positive for a reverse slant
+        #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+        slant: isize,
+        /// Strings to pick, counting from 1, e.g. "3,4,5" (default: every string)
+        #[arg(long, value_delimiter = ',')]
+        strings: Vec<usize>,
+        /// Combination before the change, e.g. "Open"
+        #[arg(long, default_value = "Open")]
+        from: String,
+        /// Combination after the change, e.g. "A+B"
+        #[arg(long)]
+        to: String,
+    },
+
+    /// Show notes on neck for given tuning
+    Notes {
+        #[command(flatten)]
+        tuning: TuningArgs,
+        #[arg(long)]
+        notes: String,
+        #[command(flatten)]
+        positions: PositionArgs,
+        #[command(flatten)]
+        neck: NeckArgs,
+        #[command(flatten)]
+        output: OutputArgs,
     },
 }
 
@@ -138,10 +318,255 @@ enum Commands {
 enum ListWhat {
     Scales,
     Chords,
+    Tunings,
+    Copedents,
+}
+
+/// An error that stops the CLI, reported on stderr with its own exit code
+enum CliError {
+    /// A tuning, note list, scale, chord or set of positions could not be understood
+    Input(String),
+    /// A copedent could not be loaded, or does not fit the guitar
+    Copedent(String),
+    /// A drawing could not be written to disk
+    Output(String),
+}
+
+impl CliError {
+    /// Exit code for the error. Clap uses 2 for command line usage errors.
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Input(_) => ExitCode::from(1),
+            Self::Copedent(_) => ExitCode::from(3),
+            Self::Output(_) => ExitCode::from(4),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Input(message) | Self::Copedent(message) | Self::Output(message) => {
+                write!(f, "{message}")
+            }
+        }
+    }
+}
+
+impl From<DisplayError> for CliError {
+    fn from(e: DisplayError) -> Self {
+        match e {
+            DisplayError::Copedent(e) => Self::Copedent(e.to_string()),
+            DisplayError::Format(_) => Self::Output(e.to_string()),
+        }
+    }
+}
+
+/// Load a copedent by preset name, or from a file if no preset has that name
+fn load_copedent(name: &str) -> Result<Copedent, CliError> {
+    match presets::copedent(name) {
+        Some(preset) => Ok(preset.load()),
+        None => Copedent::from_file(Path::new(name))
+            .map_err(|e| CliError::Copedent(format!("Invalid copedent {name}: {e}"))),
+    }
+}
+
+/// Look up a tuning preset by name
+fn tuning_preset(name: &str) -> Result<&'static TuningPreset, CliError> {
+    presets::tuning(name).ok_or_else(|| CliError::Input(format!("Unknown tuning preset: {name}")))
+}
+
+/// Build a guitar from a comma-separated tuning
+fn guitar(name: &str, notes: &str) -> Result<Guitar, CliError> {
+    Guitar::new(name, notes).map_err(|e| CliError::Input(format!("Invalid tuning {notes:?}: {e}")))
+}
+
+impl TuningArgs {
+    /// Build the guitar and the copedent to play it with. `--copedent` wins over
+    /// the preset's copedent, custom tunings default to the built-in E9 copedent
+    /// and lap steel mode uses no copedent at all.
+    fn load(
+        &self,
+        copedent: Option<&str>,
+        lap_steel: bool,
+    ) -> Result<(Guitar, Copedent), CliError> {
+        let (mut guitar, default_copedent) = match (&self.preset, &self.tuning) {
+            (Some(preset), _) => {
+                let preset = tuning_preset(preset)?;
+                let name = self.tuning_name.as_deref().unwrap_or(preset.name);
+                let guitar = Guitar {
+                    name: name.to_string(),
+                    ..preset.guitar()
+                };
+                (guitar, preset.copedent())
+            }
+            (None, Some(tuning)) => {
+                let name = self.tuning_name.as_deref().unwrap_or_default();
+                (guitar(name, tuning)?, Copedent::default())
+            }
+            (None, None) => {
+                return Err(CliError::Input(
+                    "Either --tuning or --preset is required".to_string(),
+                ));
+            }
+        };
+
+        if let Some(frets) = self.fret_count {
+            guitar.frets = frets;
+        }
+
+        let copedent = match copedent {
+            _ if lap_steel => Copedent::open(),
+            Some(copedent) => load_copedent(copedent)?,
+            None => default_copedent,
+        };
+        copedent
+            .validate_strings(guitar.tuning.len())
+            .map_err(|e| {
+                CliError::Copedent(format!("Invalid copedent for {}: {e}", guitar.name))
+            })?;
+
+        Ok((guitar, copedent))
+    }
 }
 
-fn main() {
+impl NeckArgs {
+    /// The frets to show, checked against the fretboard of the guitar
+    fn frets(&self, guitar: &Guitar) -> Result<FretRange, CliError> {
+        guitar
+            .fret_range(self.frets)
+            .map_err(|e| CliError::Input(format!("Invalid frets {}: {e}", self.frets)))
+    }
+}
+
+impl PositionArgs {
+    /// The combinations to show: the ones given with `--positions`, or every
+    /// playable combination of the copedent
+    fn positions(&self, copedent: &Copedent) -> Result<Vec<Vec<String>>, CliError> {
+        match &self.positions {
+            Some(positions) => parse_positions(copedent, positions)
+                .map_err(|e| CliError::Input(format!("Invalid positions {positions:?}: {e}"))),
+            None => {
+                let limits = CombinationLimits {
+                    max_controls: self.max_controls,
+                };
+                Ok(possible_positions(copedent, limits))
+            }
+        }
+    }
+}
+
+/// The error for asking for a drawing of something that is not a neck diagram
+fn not_a_neck_diagram(command: &str) -> CliError {
+    CliError::Input(format!(
+        "{command} has no neck diagrams to draw; use --format text or json"
+    ))
+}
+
+impl OutputArgs {
+    /// The format asked for, checking that `--out-dir` comes with `--format svg`
+    fn format(&self) -> Result<Format, CliError> {
+        match (self.format, &self.out_dir) {
+            (Format::Text | Format::Json | Format::Html, Some(_)) => Err(CliError::Input(
+                "--out-dir is only used with --format svg".to_string(),
+            )),
+            (format, _) => Ok(format),
+        }
+    }
+
+    /// Write neck diagrams as JSON, SVG or an HTML page titled `title`, with
+    /// notes labelled from `root`. With `--out-dir` each combination gets its
+    /// own SVG file, and the paths written are listed on stdout.
+    fn write(
+        &self,
+        format: Format,
+        report: &NeckReport,
+        copedent: &Copedent,
+        title: &str,
+        root: &Root,
+        options: &DisplayOptions,
+    ) -> Result<(), CliError> {
+        let label = |pos: &NeckPositions| options.label(pos, root);
+        match (format, &self.out_dir) {
+            (Format::Svg, Some(dir)) => {
+                fs::create_dir_all(dir).map_err(|e| {
+                    CliError::Output(format!("Cannot create {}: {e}", dir.display()))
+                })?;
+                for diagram in &report.diagrams {
+                    let path = dir.join(svg::file_name(diagram));
+                    let drawing = neck_sheet(
+                        report.guitar,
+                        slice::from_ref(diagram),
+                        report.frets,
+                        &label,
+                    );
+                    fs::write(&path, drawing).map_err(|e| {
+                        CliError::Output(format!("Cannot write {}: {e}", path.display()))
+                    })?;
+                    println!("{}", path.display());
+                }
+            }
+            (Format::Svg, None) => print!(
+                "{}",
+                neck_sheet(report.guitar, &report.diagrams, report.frets, &label)
+            ),
+            (Format::Html, _) => print!("{}", html_report(report, copedent, title, root, options)),
+            (Format::Json, _) => print_json(report),
+            (Format::Text, _) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Find `notes` on the neck in every position, for `--format json`, `svg` and `html`
+fn neck_report<'a>(
+    guitar: &'a Guitar,
+    copedent: &Copedent,
+    positions: &[Vec<String>],
+    notes: &[Note],
+    frets: FretRange,
+) -> Result<NeckReport<'a>, CliError> {
+    let diagrams = positions
+        .iter()
+        .map(|position| NeckDiagram::new(guitar, copedent, position, notes, frets))
+        .collect::<Result<_, _>>()
+        .map_err(|e| CliError::Copedent(e.to_string()))?;
+    Ok(NeckReport {
+        guitar,
+        frets,
+        diagrams,
+    })
+}
+
+/// Parse a single combination of pedals and levers, e.g. "A+B"
+fn parse_combination(copedent: &Copedent, s: &str) -> Result<Vec<String>, CliError> {
+    let mut positions = parse_positions(copedent, s)
+        .map_err(|e| CliError::Input(format!("Invalid position {s:?}: {e}")))?;
+    match positions.len() {
+        1 => Ok(positions.remove(0)),
+        _ => Err(CliError::Input(format!(
+            "Invalid position {s:?}: expected one combination, e.g. \"A+B\""
+        ))),
+    }
+}
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            e.exit_code()
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    let options = DisplayOptions {
+        octaves: cli.octaves,
+        color: cli.color.enabled(),
+        ..Default::default()
+    };
 
     match cli.command {
         Commands::List { what } => match what {
@@ -151,69 +576,298 @@ fn main() {
                 }
             }
             ListWhat::Chords => {
-                for c in AVAILABLE_CHORDS {
-                    println!("{}", c);
+                for (_, _, name) in CHORD_TYPES {
+                    println!("{name}");
+                }
+            }
+            ListWhat::Tunings => {
+                for t in presets::TUNINGS {
+                    println!("{:<14} {:<26} {}", t.id, t.name, t.notes);
+                }
+            }
+            ListWhat::Copedents => {
+                for c in presets::COPEDENTS {
+                    println!("{:<10} {}", c.id, c.description);
                 }
             }
         },
 
-        Commands::Tuning { notes } => {
-            let guitar = Guitar::new("cli", &notes);
-            print_tuning(&guitar.tuning);
+        Commands::Tuning {
+            notes,
+            preset,
+            format,
+        } => {
+            let guitar = match preset {
+                Some(preset) => tuning_preset(&preset)?.guitar(),
+                None => guitar("", &notes.unwrap_or_default())?,
+            };
+            match format {
+                Format::Text => print_tuning(&guitar.tuning, &options),
+                Format::Json => print_json(&guitar),
+                Format::Svg | Format::Html => return Err(not_a_neck_diagram("tuning")),
+            }
         }
 
-        Commands::Copedent => {
-            print_copedent();
+        Commands::Copedent {
+            tuning,
+            preset,
+            format,
+        } => {
+            let (copedent, string_count) = match (preset, tuning) {
+                (Some(preset), _) => {
+                    let preset = tuning_preset(&preset)?;
+                    let copedent = match &cli.copedent {
+                        Some(copedent) => load_copedent(copedent)?,
+                        None => preset.copedent(),
+                    };
+                    (copedent, preset.guitar().tuning.len())
+                }
+                (None, tuning) => {
+                    let copedent = match &cli.copedent {
+                        Some(copedent) => load_copedent(copedent)?,
+                        None => Copedent::default(),
+                    };
+                    let string_count = match tuning {
+                        Some(tuning) => guitar("cli", &tuning)?.tuning.len(),
+                        None => copedent.string_count(),
+                    };
+                    (copedent, string_count)
+                }
+            };
+            copedent
+                .validate_strings(string_count)
+                .map_err(|e| CliError::Copedent(format!("Invalid copedent: {e}")))?;
+            match format {
+                Format::Text => print_copedent(&copedent, string_count),
+                Format::Json => print_json(&copedent),
+                Format::Svg | Format::Html => return Err(not_a_neck_diagram("copedent")),
+            }
         }
 
         Commands::Scale {
-            tuning_name,
             tuning,
             scale,
-            lap_steel: _,
+            positions,
+            neck,
+            output,
         } => {
-            let guitar = Guitar::new(&tuning_name, &tuning);
-            match Scale::from_regex(&scale) {
-                Ok(scale_obj) => {
-                    print_scale(&guitar, &[Position::Open], &scale_obj);
+            // In lap steel mode the copedent is empty, so only the Open position is shown
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), positions.lap_steel)?;
+            let frets = neck.frets(&guitar)?;
+            let options = DisplayOptions {
+                frets,
+                label: neck.label,
+                ..options
+            };
+            let positions = positions.positions(&copedent)?;
+            let scale_obj = Scale::from_regex(&scale)
+                .map_err(|_| CliError::Input(format!("Invalid scale: {scale}")))?;
+            let root = Root::Note(scale_obj.tonic);
+            match output.format()? {
+                Format::Text => {
+                    for position in positions {
+                        print_scale(&guitar, &copedent, &position, &scale_obj, &options)?;
+                    }
+                }
+                format => {
+                    let notes = scale_notes(&scale_obj);
+                    let report = neck_report(&guitar, &copedent, &positions, &notes, frets)?;
+                    output.write(format, &report, &copedent, &scale, &root, &options)?;
                 }
-                Err(_) => eprintln!("Invalid scale: {}", scale),
             }
         }
 
         Commands::Chord {
-            tuning_name,
             tuning,
             chord,
-            lap_steel,
+            positions,
+            neck,
+            output,
         } => {
-            let guitar = Guitar::new(&tuning_name, &tuning);
-            match Chord::from_regex(&chord) {
-                Ok(chord_obj) => {
-                    // print chord positions
-                    // If lap_steel mode, only show Open position, otherwise show all positions
-                    let positions = if lap_steel {
-                        vec![vec![Position::Open]]
-                    } else {
-                        possible_positions()
-                    };
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), positions.lap_steel)?;
+            let frets = neck.frets(&guitar)?;
+            let options = DisplayOptions {
+                frets,
+                label: neck.label,
+                ..options
+            };
+            let positions = positions.positions(&copedent)?;
+            let chord_obj = parse_chord(&chord)
+                .ok_or_else(|| CliError::Input(format!("Invalid chord: {chord}")))?;
+            let root = Root::Chord(&chord_obj);
+            match output.format()? {
+                // print chord positions
+                Format::Text => {
                     for position in positions {
-                        print_chord(&guitar, &position, &chord_obj);
-                        print_chord_on_pedal_steel(&guitar, &position, &chord_obj);
+                        print_chord(&guitar, &copedent, &position, &chord_obj, &options)?;
+                        print_chord_on_pedal_steel(
+                            &guitar, &copedent, &position, &chord_obj, &options,
+                        )?;
                     }
                 }
-                Err(_) => eprintln!("Invalid chord: {}", chord),
+                format => {
+                    let notes = chord_notes(&chord_obj);
+                    let report = neck_report(&guitar, &copedent, &positions, &notes, frets)?;
+                    let title = chord_name(&chord_obj);
+                    output.write(format, &report, &copedent, &title, &root, &options)?;
+                }
+            }
+        }
+
+        Commands::Grips {
+            tuning,
+            chord,
+            limit,
+            slant,
+            diagrams,
+            positions,
+            neck,
+        } => {
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), positions.lap_steel)?;
+            let frets = neck.frets(&guitar)?;
+            let options = DisplayOptions {
+                frets,
+                label: neck.label,
+                ..options
+            };
+            let positions = positions.positions(&copedent)?;
+            let chord_obj = parse_chord(&chord)
+                .ok_or_else(|| CliError::Input(format!("Invalid chord: {chord}")))?;
+            for position in positions {
+                let mut grips = find_grips(&guitar, &copedent, &position, &chord_obj, frets, slant)
+                    .map_err(|e| CliError::Copedent(e.to_string()))?;
+                grips.truncate(limit);
+                print_grips(&guitar, &position, &chord_obj, &grips, &options);
+                if diagrams {
+                    for grip in &grips {
+                        print_grip_diagram(&guitar, &position, &chord_obj, grip, &options)?;
+                    }
+                }
+            }
+        }
+
+        Commands::Progression {
+            tuning,
+            chords,
+            key,
+            positions,
+            neck,
+        } => {
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), positions.lap_steel)?;
+            let frets = neck.frets(&guitar)?;
+            let options = DisplayOptions {
+                frets,
+                label: neck.label,
+                ..options
+            };
+            let positions = positions.positions(&copedent)?;
+            let chords = parse_progression(&chords, key.as_deref())
+                .map_err(|e| CliError::Input(format!("Invalid progression: {e}")))?;
+            let steps =
+                plan_progression(&guitar, &copedent, &positions, &chords, frets).map_err(|e| {
+                    match e {
+                        ProgressionError::Copedent(e) => CliError::Copedent(e.to_string()),
+                        e => CliError::Input(e.to_string()),
+                    }
+                })?;
+            print_progression(&guitar, &steps, &options)?;
+        }
+
+        Commands::Identify {
+            tuning,
+            fret,
+            slant,
+            strings,
+            positions,
+        } => {
+            let bar = Bar { fret, slant };
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), positions.lap_steel)?;
+            let grips = positions
+                .positions(&copedent)?
+                .into_iter()
+                .map(|position| {
+                    identify_chords(&guitar, &copedent, &position, bar, &strings)
+                        .map(|identified| (position, identified))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| match e {
+                    IdentifyError::Copedent(e) => CliError::Copedent(e.to_string()),
+                    e => CliError::Input(e.to_string()),
+                })?;
+
+            let strings: Vec<String> = strings.iter().map(ToString::to_string).collect();
+            println!(
+                "{}, bar at {bar}, strings {}",
+                guitar.name,
+                strings.join(", ")
+            );
+            for (position, identified) in grips {
+                print_chord_matches(&position, &identified.midi, &identified.chords, &options);
             }
         }
 
+        Commands::VoiceLeading {
+            tuning,
+            fret,
+            slant,
+            strings,
+            from,
+            to,
+        } => {
+            let bar = Bar { fret, slant };
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), false)?;
+            let strings = if strings.is_empty() {
+                (1..=guitar.tuning.len()).collect()
+            } else {
+                strings
+            };
+            let from = parse_combination(&copedent, &from)?;
+            let to = parse_combination(&copedent, &to)?;
+            let leading = voice_leading(&guitar, &copedent, &from, &to, bar, &strings).map_err(
+                |e| match e {
+                    IdentifyError::Copedent(e) => CliError::Copedent(e.to_string()),
+                    e => CliError::Input(e.to_string()),
+                },
+            )?;
+            print_voice_leading(&guitar, &from, &to, bar, &leading, &options);
+        }
+
         Commands::Notes {
-            tuning_name,
             tuning,
             notes,
-            lap_steel: _,
+            positions,
+            neck,
+            output,
         } => {
-            let guitar = Guitar::new(&tuning_name, &tuning);
-            print_notes_on_neck(&guitar, &[Position::Open], &notes);
+            let (guitar, copedent) = tuning.load(cli.copedent.as_deref(), positions.lap_steel)?;
+            let frets = neck.frets(&guitar)?;
+            let options = DisplayOptions {
+                frets,
+                label: neck.label,
+                ..options
+            };
+            let pitches = parse_tuning(&notes)
+                .map_err(|e| CliError::Input(format!("Invalid notes {notes:?}: {e}")))?;
+            let positions = positions.positions(&copedent)?;
+            match output.format()? {
+                Format::Text => {
+                    for position in positions {
+                        print_notes_on_neck(&guitar, &copedent, &position, &pitches, &options)?;
+                    }
+                }
+                format => {
+                    // Labels count from the first note given
+                    let root = Root::Note(pitches[0]);
+                    let names: Vec<String> = pitches.iter().map(ToString::to_string).collect();
+                    let title = format!("Notes {}", names.join(", "));
+                    let notes: Vec<Note> =
+                        pitches.iter().map(|&pitch| Note::new(pitch, 0)).collect();
+                    let report = neck_report(&guitar, &copedent, &positions, &notes, frets)?;
+                    output.write(format, &report, &copedent, &title, &root, &options)?;
+                }
+            }
         }
     }
+
+    Ok(())
 }
@@ -0,0 +1,321 @@
+//! Presets — built-in tunings and copedents.
+//!
+//! Named steel guitar tunings (pedal steel, lap steel and resonator) and the
+//! common E9 copedents, so they can be selected by name instead of retyped.
+
+use crate::{
+    copedent::{Copedent, DEFAULT_COPEDENT},
+    guitar::{DEFAULT_FRETS, DEFAULT_SCALE_LENGTH, Guitar},
+};
+
+/// Scale length of a typical lap steel, in inches
+const LAP_STEEL_SCALE_LENGTH: f64 = 22.5;
+
+/// Number of frets marked on a typical lap steel fretboard
+const LAP_STEEL_FRETS: usize = 22;
+
+/// Scale length of a square-neck resonator guitar, in inches
+const RESONATOR_SCALE_LENGTH: f64 = 25.0;
+
+/// Number of frets marked on a square-neck resonator fretboard
+const RESONATOR_FRETS: usize = 19;
+
+/// A named tuning shipped with the tool
+#[derive(Debug)]
+pub struct TuningPreset {
+    /// Short identifier used on the command line, e.g. "e9"
+    pub id: &'static str,
+    /// Display name, e.g. "E9"
+    pub name: &'static str,
+    /// Comma-separated notes with their octaves, string 1 first
+    pub notes: &'static str,
+    /// Short description of the instrument the tuning is for
+    pub description: &'static str,
+    /// Id of the copedent preset used with this tuning, if it is played with pedals
+    pub copedent: Option<&'static str>,
+    /// Distance from the nut to the bridge, in inches
+    pub scale_length: f64,
+    /// Number of frets on the fretboard
+    pub frets: usize,
+}
+
+/// A named copedent shipped with the tool
+#[derive(Debug)]
+pub struct CopedentPreset {
+    /// Short identifier used on the command line, e.g. "emmons"
+    pub id: &'static str,
+    /// Short description of the setup
+    pub description: &'static str,
+    /// The copedent definition in TOML
+    source: &'static str,
+}
+
+/// Built-in tunings
+pub const TUNINGS: &[TuningPreset] = &[
+    TuningPreset {
+        id: "e9",
+        name: "E9",
+        notes: "F#4, D#4, G#4, E4, B3, G#3, F#3, E3, D3, B2",
+        description: "10-string E9 pedal steel",
+        copedent: Some("e9"),
+        scale_length: DEFAULT_SCALE_LENGTH,
+        frets: DEFAULT_FRETS,
+    },
+    TuningPreset {
+        id: "c6",
+        name: "C6",
+        notes: "D4, E4, C4, A3, G3, E3, C3, A2, F2, C2",
+        description: "10-string C6 pedal steel",
+        copedent: None,
+        scale_length: DEFAULT_SCALE_LENGTH,
+        frets: DEFAULT_FRETS,
+    },
+    TuningPreset {
+        id: "e9-b6",
+        name: "E9/B6 Universal",
+        notes: "B3, D#4, G#4, E4, B3, G#3, F#3, E3, D3, B2, G#2, E2",
+        description: "12-string E9/B6 universal pedal steel",
+        copedent: None,
+        scale_length: DEFAULT_SCALE_LENGTH,
+        frets: DEFAULT_FRETS,
+    },
+    TuningPreset {
+        id: "a6",
+        name: "A6",
+        notes: "E4, C#4, A3, F#3, E3, C#3, A2, F#2",
+        description: "8-string A6 lap steel",
+        copedent: None,
+        scale_length: LAP_STEEL_SCALE_LENGTH,
+        frets: LAP_STEEL_FRETS,
+    },
+    TuningPreset {
+        id: "c6-lap",
+        name: "C6 Lap",
+        notes: "E4, C4, A3, G3, E3, C3, A2, G2",
+        description: "8-string C6 lap steel",
+        copedent: None,
+        scale_length: LAP_STEEL_SCALE_LENGTH,
+        frets: LAP_STEEL_FRETS,
+    },
+    TuningPreset {
+        id: "c6-cashdollar",
+        name: "C6 (Cindy Cashdollar)",
+        notes: "G4, E4, C4, A3, G3, E3, C3, A2",
+        description: "8-string C6 lap steel with a high G",
+        copedent: None,
+        scale_length: LAP_STEEL_SCALE_LENGTH,
+        frets: LAP_STEEL_FRETS,
+    },
+    TuningPreset {
+        id: "e13-helms",
+        name: "E13 (Don Helms)",
+        notes: "G#4, E4, C#4, B3, G#3, E3, C#3, A2",
+        description: "8-string E13 lap steel",
+        copedent: None,
+        scale_length: LAP_STEEL_SCALE_LENGTH,
+        frets: LAP_STEEL_FRETS,
+    },
+    TuningPreset {
+        id: "e13-wiggins",
+        name: "E13 (Little Roy Wiggins)",
+        notes: "G#4, E4, C#4, B3, G#3, E3, D3, B2",
+        description: "8-string E13 lap steel",
+        copedent: None,
+        scale_length: LAP_STEEL_SCALE_LENGTH,
+        frets: LAP_STEEL_FRETS,
+    },
+    TuningPreset {
+        id: "dobro-g",
+        name: "Dobro G",
+        notes: "D4, B3, G3, D3, B2, G2",
+        description: "6-string resonator in open G",
+        copedent: None,
+        scale_length: RESONATOR_SCALE_LENGTH,
+        frets: RESONATOR_FRETS,
+    },
+];
+
+/// Built-in copedents
+pub const COPEDENTS: &[CopedentPreset] = &[
+    CopedentPreset {
+        id: "e9",
+        description: "Built-in E9 copedent used when no copedent is given",
+        source: DEFAULT_COPEDENT,
+    },
+    CopedentPreset {
+        id: "emmons",
+        description: "Emmons setup E9: pedals A, B, C; E raises on LKL, E lowers on LKR",
+        source: include_str!("../copedents/emmons.toml"),
+    },
+    CopedentPreset {
+        id: "day",
+        description: "Day setup E9: the Emmons changes with pedals ordered B, A, C",
+        source: include_str!("../copedents/day.toml"),
+    },
+    CopedentPreset {
+        id: "franklin",
+        description: "Franklin style E9: the Emmons changes plus a fourth pedal",
+        source: include_str!("../copedents/franklin.toml"),
+    },
+];
+
+impl TuningPreset {
+    /// The guitar this tuning is for, with its scale length and frets
+    pub fn guitar(&self) -> Guitar {
+        Guitar {
+            scale_length: self.scale_length,
+            frets: self.frets,
+            ..Guitar::new(self.name, self.notes).expect("built-in tuning is valid")
+        }
+    }
+
+    /// The copedent played with this tuning. Tunings without pedals get a
+    /// copedent with no controls, so only the Open position is shown.
+    pub fn copedent(&self) -> Copedent {
+        self.copedent
+            .and_then(copedent)
+            .map_or_else(Copedent::open, CopedentPreset::load)
+    }
+}
+
+impl CopedentPreset {
+    /// Parse the built-in copedent
+    pub fn load(&self) -> Copedent {
+        Copedent::from_toml_str(self.source).expect("built-in copedent is valid")
+    }
+}
+
+/// Find a tuning preset by id or name, ignoring case
+pub fn tuning(name: &str) -> Option<&'static TuningPreset> {
+    TUNINGS.iter().find(|preset| {
+        preset.id.eq_ignore_ascii_case(name) || preset.name.eq_ignore_ascii_case(name)
+    })
+}
+
+/// Find a copedent preset by id, ignoring case
+pub fn copedent(name: &str) -> Option<&'static CopedentPreset> {
+    COPEDENTS
+        .iter()
+        .find(|preset| preset.id.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        guitar::{FretRange, FretRangeError},
+        spelling::spell_change,
+        tunings::{Tuning, tuning as parse_tuning},
+    };
+
+    #[test]
+    fn test_tuning_lookup() {
+        assert_eq!(tuning("E9").unwrap().id, "e9");
+        assert_eq!(tuning("e13 (don helms)").unwrap().id, "e13-helms");
+        assert_eq!(tuning("dobro-g").unwrap().name, "Dobro G");
+        assert!(tuning("E7").is_none());
+    }
+
+    #[test]
+    fn test_tuning_presets_parse() {
+        for preset in TUNINGS {
+            let string_count = preset.notes.split(',').count();
+            assert_eq!(
+                parse_tuning(preset.notes).unwrap().len(),
+                string_count,
+                "{}",
+                preset.id
+            );
+            assert!(
+                preset.copedent().validate_strings(string_count).is_ok(),
+                "{}",
+                preset.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_tuning_presets_are_in_their_octaves() {
+        let midi = |id: &str| -> Vec<u8> {
+            let tuning: Tuning = tuning(id).unwrap().notes.parse().unwrap();
+            tuning.strings.iter().map(|string| string.midi).collect()
+        };
+        // F#4 down to B2
+        assert_eq!(midi("e9"), [66, 63, 68, 64, 59, 56, 54, 52, 50, 47]);
+        // The E9 strings with a low B on string 1 and G# and E below
+        assert_eq!(
+            midi("e9-b6"),
+            [59, 63, 68, 64, 59, 56, 54, 52, 50, 47, 44, 40]
+        );
+        assert_eq!(midi("c6"), [62, 64, 60, 57, 55, 52, 48, 45, 41, 36]);
+        assert_eq!(midi("dobro-g"), [62, 59, 55, 50, 47, 43]);
+        for preset in TUNINGS {
+            let tuning: Tuning = preset.notes.parse().unwrap();
+            assert!(
+                tuning
+                    .strings
+                    .iter()
+                    .all(|string| (36..=72).contains(&string.midi)),
+                "{}",
+                preset.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_copedent_presets_load() {
+        for preset in COPEDENTS {
+            let copedent = preset.load();
+            assert!(!copedent.controls.is_empty(), "{}", preset.id);
+            assert!(copedent.validate_strings(10).is_ok(), "{}", preset.id);
+        }
+        assert_eq!(copedent("Franklin").unwrap().load().controls.len(), 9);
+    }
+
+    #[test]
+    fn test_copedent_preset_labels_are_spelled() {
+        // Every built-in copedent is for the E9 strings
+        let open = parse_tuning(tuning("E9").unwrap().notes).unwrap();
+        for preset in COPEDENTS {
+            for control in preset.load().controls {
+                for change in &control.changes {
+                    let from = open[usize::from(change.string) - 1];
+                    let to = spell_change(from, change.semitone_change);
+                    assert_eq!(
+                        change.label.as_deref(),
+                        Some(format!("{from}→{to}").as_str()),
+                        "{} {} string {}",
+                        preset.id,
+                        control.name,
+                        change.string
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tuning_presets_carry_their_fretboard() {
+        let e9 = tuning("E9").unwrap().guitar();
+        assert_eq!((e9.frets, e9.scale_length), (24, 24.0));
+        assert_eq!(e9.name, "E9");
+
+        let lap = tuning("C6 Lap").unwrap().guitar();
+        assert_eq!((lap.frets, lap.scale_length), (22, 22.5));
+        let range: FretRange = "0..24".parse().unwrap();
+        assert_eq!(
+            lap.fret_range(range),
+            Err(FretRangeError::PastLastFret { end: 24, frets: 22 })
+        );
+        let range: FretRange = "0..22".parse().unwrap();
+        assert_eq!(lap.fret_range(range), Ok(range));
+
+        assert_eq!(tuning("dobro-g").unwrap().guitar().frets, 19);
+    }
+
+    #[test]
+    fn test_lap_steel_presets_have_no_controls() {
+        assert!(tuning("A6").unwrap().copedent().controls.is_empty());
+        assert_eq!(tuning("E9").unwrap().copedent().controls.len(), 9);
+    }
+}
@@ -0,0 +1,424 @@
+//! Chord progressions — planning how to play a run of chords.
+//!
+//! A progression is given as chord names ("A major, D major, E major") or as
+//! Nashville numbers counted from a key ("1 4 5"). Every chord is voiced with
+//! the grip finder in every combination of pedals and levers, and the planner
+//! picks one grip per chord so the bar travels as little as possible and as
+//! few pedals and levers change between chords as possible.
+
+use crate::{
+    chords::{Grip, chord_name, find_grips, parse_chord},
+    copedent::{Copedent, CopedentError, control_changes},
+    guitar::{FretRange, Guitar},
+    spelling::{MAJOR_SCALE, parse_note_name, spell_degree},
+};
+use rust_music_theory::{
+    chord::{Chord, Number, Quality},
+    note::Pitch,
+};
+use std::fmt;
+
+/// Cost of moving the bar by one fret between chords
+const BAR_TRAVEL_COST: usize = 1;
+
+/// Cost of engaging or releasing one pedal or lever between chords
+const CONTROL_CHANGE_COST: usize = 2;
+
+/// Cost of a grip without the root in the bass, so that of two otherwise
+/// equal plans the one with more root position chords wins
+const INVERSION_COST: usize = 1;
+
+/// Errors that can occur while planning a progression
+#[derive(Debug)]
+pub enum ProgressionError {
+    /// No chords were given
+    Empty,
+    /// A chord name or Nashville number cannot be understood
+    InvalidChord(String),
+    /// The key is not a note name
+    InvalidKey(String),
+    /// A chord has no grip in any combination within the frets searched
+    NoGrip(String),
+    /// A combination cannot be played on the copedent
+    Copedent(CopedentError),
+}
+
+impl fmt::Display for ProgressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no chords given"),
+            Self::InvalidChord(chord) => write!(f, "{chord:?} is not a chord"),
+            Self::InvalidKey(key) => write!(f, "{key:?} is not a key"),
+            Self::NoGrip(chord) => write!(f, "no grip found for {chord}"),
+            Self::Copedent(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ProgressionError {}
+
+impl From<CopedentError> for ProgressionError {
+    fn from(e: CopedentError) -> Self {
+        Self::Copedent(e)
+    }
+}
+
+/// One chord of a planned progression and how to play it
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub chord: Chord,
+    /// The pedals and levers engaged
+    pub position: Vec<String>,
+    /// The strings picked and where the bar is
+    pub grip: Grip,
+}
+
+impl Step {
+    /// How far the bar moves from the previous step, in frets (up the neck
+    /// is positive)
+    pub fn bar_travel(&self, previous: &Step) -> isize {
+        let fret = |step: &Step| isize::try_from(step.grip.fret()).unwrap_or(isize::MAX);
+        fret(self) - fret(previous)
+    }
+
+    /// How many pedals and levers change from the previous step
+    pub fn control_changes(&self, previous: &Step) -> usize {
+        control_changes(&previous.position, &self.position)
+    }
+
+    /// The cost of getting to this step from the previous one
+    fn cost(&self, previous: &Step) -> usize {
+        self.bar_travel(previous).unsigned_abs() * BAR_TRAVEL_COST
+            + self.control_changes(previous) * CONTROL_CHANGE_COST
+    }
+}
+
+/// Parse a Nashville number such as "1", "4", "6m", "57", "b7" or "2m7" in
+/// `key`. Numbers count up the major scale of the key; the chord is major
+/// unless a suffix says otherwise.
+pub fn nashville_chord(number: &str, key: Pitch) -> Option<Chord> {
+    let (alteration, rest) = match number.chars().next()? {
+        'b' | '♭' => (-1, &number[number.char_indices().nth(1)?.0..]),
+        '#' | '♯' => (1, &number[number.char_indices().nth(1)?.0..]),
+        _ => (0, number),
+    };
+    let degree = rest.chars().next()?.to_digit(10)?;
+    if !(1..=7).contains(&degree) {
+        return None;
+    }
+    let degree = u8::try_from(degree).ok()?;
+
+    let (quality, chord_number) = match &rest[1..] {
+        "" => (Quality::Major, Number::Triad),
+        "m" | "-" | "mi" => (Quality::Minor, Number::Triad),
+        "7" => (Quality::Dominant, Number::Seventh),
+        "maj7" | "M7" | "Δ" | "Δ7" => (Quality::Major, Number::Seventh),
+        "m7" | "-7" | "mi7" => (Quality::Minor, Number::Seventh),
+        "dim" | "°" | "o" => (Quality::Diminished, Number::Triad),
+        "dim7" | "°7" | "o7" => (Quality::Diminished, Number::Seventh),
+        "ø" | "ø7" | "m7b5" => (Quality::HalfDiminished, Number::Seventh),
+        "+" | "aug" => (Quality::Augmented, Number::Triad),
+        "sus2" => (Quality::Suspended2, Number::Triad),
+        "sus" | "sus4" => (Quality::Suspended4, Number::Triad),
+        "9" => (Quality::Dominant, Number::Ninth),
+        "maj9" => (Quality::Major, Number::Ninth),
+        _ => return None,
+    };
+
+    let major = MAJOR_SCALE[usize::from(degree - 1)];
+    let semitones = u8::try_from((i16::from(major) + alteration).rem_euclid(12)).ok()?;
+    let root = spell_degree(key, semitones, degree);
+    Some(Chord::new(root, quality, chord_number))
+}
+
+/// Parse a progression. Without a key, chords are names separated by commas
+/// ("A major, D major"); with a key they are Nashville numbers separated by
+/// commas or spaces ("1 4 5").
+pub fn parse_progression(chords: &str, key: Option<&str>) -> Result<Vec<Chord>, ProgressionError> {
+    let chords: Vec<Chord> = match key {
+        Some(key) => {
+            let key = parse_note_name(key.trim())
+                .ok_or_else(|| ProgressionError::InvalidKey(key.to_string()))?;
+            chords
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|number| !number.is_empty())
+                .map(|number| {
+                    nashville_chord(number, key)
+                        .ok_or_else(|| ProgressionError::InvalidChord(number.to_string()))
+                })
+                .collect::<Result<_, _>>()?
+        }
+        None => chords
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                parse_chord(name).ok_or_else(|| ProgressionError::InvalidChord(name.to_string()))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    if chords.is_empty() {
+        return Err(ProgressionError::Empty);
+    }
+    Ok(chords)
+}
+
+/// The ways to play a chord: its most complete grips in every combination
+fn candidates(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    positions: &[Vec<String>],
+    chord: &Chord,
+    frets: FretRange,
+) -> Result<Vec<Step>, ProgressionError> {
+    let mut steps = Vec::new();
+    for position in positions {
+        for grip in find_grips(guitar, copedent, position, chord, frets, 0)? {
+            steps.push(Step {
+                chord: chord.clone(),
+                position: position.clone(),
+                grip,
+            });
+        }
+    }
+
+    // Only keep the most complete grips
+    let fewest_missing = steps
+        .iter()
+        .map(|step| step.grip.missing.len())
+        .min()
+        .ok_or_else(|| ProgressionError::NoGrip(chord_name(chord)))?;
+    steps.retain(|step| step.grip.missing.len() == fewest_missing);
+    Ok(steps)
+}
+
+/// Plan how to play a progression: one grip per chord, chosen from the
+/// combinations in `positions` and the frets in `frets`, so the total bar
+/// travel and pedal and lever changes are as small as possible. Complete grips
+/// are used whenever a chord has one.
+pub fn plan_progression(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    positions: &[Vec<String>],
+    chords: &[Chord],
+    frets: FretRange,
+) -> Result<Vec<Step>, ProgressionError> {
+    let candidates = chords
+        .iter()
+        .map(|chord| candidates(guitar, copedent, positions, chord, frets))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some((first, rest)) = candidates.split_first() else {
+        return Err(ProgressionError::Empty);
+    };
+
+    let own_cost = |step: &Step| {
+        if step.grip.root_in_bass {
+            0
+        } else {
+            INVERSION_COST
+        }
+    };
+
+    // Cheapest way to reach each candidate of the current chord, and which
+    // candidate of the previous chord it came from
+    let mut costs: Vec<usize> = first.iter().map(own_cost).collect();
+    let mut came_from: Vec<Vec<usize>> = Vec::new();
+    let mut previous = first;
+    for steps in rest {
+        let (step_costs, step_from): (Vec<usize>, Vec<usize>) = steps
+            .iter()
+            .map(|step| {
+                previous
+                    .iter()
+                    .zip(&costs)
+                    .enumerate()
+                    .map(|(i, (before, cost))| (cost + step.cost(before) + own_cost(step), i))
+                    .min()
+                    .unwrap_or((usize::MAX, 0))
+            })
+            .unzip();
+        costs = step_costs;
+        came_from.push(step_from);
+        previous = steps;
+    }
+
+    // Walk back from the cheapest last step
+    let mut index = costs
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, cost)| cost)
+        .map_or(0, |(i, _)| i);
+    let mut plan = vec![candidates[candidates.len() - 1][index].clone()];
+    for (steps, from) in candidates.iter().rev().skip(1).zip(came_from.iter().rev()) {
+        index = from[index];
+        plan.push(steps[index].clone());
+    }
+    plan.reverse();
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        copedent::{position_name, possible_positions},
+        test_support::e9,
+    };
+
+    /// Fret, combination and strings of each step
+    fn plan_summary(plan: &[Step]) -> Vec<(usize, String, Vec<usize>)> {
+        plan.iter()
+            .map(|step| {
+                (
+                    step.grip.fret(),
+                    position_name(&step.position),
+                    step.grip.strings(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nashville_chord() {
+        let e = parse_note_name("E").unwrap();
+        let name = |number| nashville_chord(number, e).map(|chord| chord_name(&chord));
+        assert_eq!(name("1").unwrap(), "E Major Triad");
+        assert_eq!(name("4").unwrap(), "A Major Triad");
+        assert_eq!(name("57").unwrap(), "B Dominant Seventh");
+        assert_eq!(name("6m").unwrap(), "C# Minor Triad");
+        assert_eq!(name("2m7").unwrap(), "F# Minor Seventh");
+        assert_eq!(name("b7").unwrap(), "D Major Triad");
+        assert_eq!(name("7ø").unwrap(), "D# Half Diminished Seventh");
+        assert!(name("8").is_none());
+        assert!(name("4x").is_none());
+        assert!(name("").is_none());
+
+        let f = parse_note_name("F").unwrap();
+        assert_eq!(
+            nashville_chord("4", f).map(|c| chord_name(&c)).unwrap(),
+            "Bb Major Triad"
+        );
+    }
+
+    #[test]
+    fn test_parse_progression() {
+        let chords = parse_progression("A major, D major, E dominant seventh", None).unwrap();
+        assert_eq!(
+            chords.iter().map(chord_name).collect::<Vec<_>>(),
+            ["A Major Triad", "D Major Triad", "E Dominant Seventh"]
+        );
+        let chords = parse_progression("2m7 57, 1", Some("C")).unwrap();
+        assert_eq!(
+            chords.iter().map(chord_name).collect::<Vec<_>>(),
+            ["D Minor Seventh", "G Dominant Seventh", "C Major Triad"]
+        );
+        assert!(matches!(
+            parse_progression(" , ", None),
+            Err(ProgressionError::Empty)
+        ));
+        assert!(matches!(
+            parse_progression("1 9", Some("E")),
+            Err(ProgressionError::InvalidChord(_))
+        ));
+        assert!(matches!(
+            parse_progression("1", Some("J")),
+            Err(ProgressionError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_plan_progression_uses_the_pedals() {
+        let guitar = e9();
+        let copedent = Copedent::default();
+        let positions = possible_positions(&copedent, Default::default());
+        let chords = parse_progression("1 4 5 1", Some("E")).unwrap();
+        let plan = plan_progression(
+            &guitar,
+            &copedent,
+            &positions,
+            &chords,
+            FretRange::first_twelve(),
+        )
+        .unwrap();
+
+        // A and B stay down throughout: I at the second fret, IV at the nut, V
+        // back at the second fret and I again without moving
+        assert_eq!(
+            plan_summary(&plan),
+            [
+                (2, "A & B".to_string(), vec![6, 7, 9]),
+                (0, "A & B".to_string(), vec![3, 4, 5, 6]),
+                (2, "A & B".to_string(), vec![3, 4, 5, 6]),
+                (2, "A & B".to_string(), vec![6, 7, 9]),
+            ]
+        );
+        assert!(plan.iter().all(|step| step.grip.is_complete()));
+        assert!(plan.iter().all(|step| step.grip.root_in_bass));
+        assert!(plan.windows(2).all(|w| w[1].control_changes(&w[0]) == 0));
+    }
+
+    #[test]
+    fn test_plan_progression_prefers_complete_grips() {
+        let guitar = e9();
+        let copedent = Copedent::default();
+        let chords = parse_progression("1 4", Some("E")).unwrap();
+        let frets = FretRange { start: 0, end: 5 };
+
+        // Without pedals, E and C# on strings 9 and 10 at the second fret are
+        // the closest A major, but they leave out the root
+        let a_major = &chords[1];
+        let grips = find_grips(&guitar, &copedent, &[], a_major, frets, 0).unwrap();
+        assert!(
+            grips
+                .iter()
+                .any(|g| g.fret() == 2 && g.strings() == [9, 10] && !g.is_complete())
+        );
+
+        let plan = plan_progression(&guitar, &copedent, &[vec![]], &chords, frets).unwrap();
+        assert_eq!(
+            plan_summary(&plan),
+            [
+                (0, "Open".to_string(), vec![4, 5, 6, 8]),
+                (5, "Open".to_string(), vec![4, 5, 6, 8]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_progression_prefers_the_root_in_the_bass() {
+        let guitar = e9();
+        let copedent = Copedent::default();
+        let chords = parse_progression("1", Some("E")).unwrap();
+        // At the nut RKL only leaves E major with B in the bass, on strings 3 to
+        // 5; it is tried first but the open strings cost the same with E in the bass
+        let positions = [vec!["RKL".to_string()], vec![]];
+        let plan = plan_progression(
+            &guitar,
+            &copedent,
+            &positions,
+            &chords,
+            FretRange { start: 0, end: 0 },
+        )
+        .unwrap();
+        assert_eq!(
+            plan_summary(&plan),
+            [(0, "Open".to_string(), vec![4, 5, 6, 8])]
+        );
+        assert!(plan[0].grip.root_in_bass);
+    }
+
+    #[test]
+    fn test_plan_progression_without_grips() {
+        let guitar = Guitar::new("One string", "E").unwrap();
+        let chords = parse_progression("C major seventh", None).unwrap();
+        let result = plan_progression(
+            &guitar,
+            &Copedent::default(),
+            &[vec![]],
+            &chords,
+            FretRange::first_twelve(),
+        );
+        assert!(matches!(result, Err(ProgressionError::NoGrip(_))));
+    }
+}
@@ -0,0 +1,170 @@
+//! Reports — what the CLI shows, as plain data for `--format json`.
+//!
+//! Scripts read this output, so its layout is kept stable: fields may be added
+//! but are not renamed or removed. Notes are written as their name ("G#") with
+//! the MIDI note they sound, strings count from 1 as on the command line, and
+//! a position is the list of pedals and levers engaged (empty for Open).
+//!
+//! `scale`, `chord` and `notes` write a [`NeckReport`]:
+//!
+//! ```text
+//! {
+//!   "guitar": { "name": "E9", "tuning": [{ "pitch": "F#", "midi": 66 }, ...],
+//!               "scale_length": 24.0, "frets": 24 },
+//!   "frets": { "start": 0, "end": 11 },
+//!   "diagrams": [
+//!     { "position": ["A", "B"], "name": "A & B",
+//!       "notes": [{ "pitch": "E", "midi": 64, "note_name": "E",
+//!                   "string": 4, "fret": 0 }, ...] }
+//!   ]
+//! }
+//! ```
+//!
+//! `tuning` writes the [`Guitar`] alone. `copedent` writes the [`Copedent`] in
+//! the layout of a copedent file, so its output can be loaded with
+//! `--copedent` again.
+
+use crate::{
+    copedent::{Copedent, CopedentError, position_name},
+    guitar::{FretRange, Guitar, NeckPositions, identify_notes_on_neck},
+};
+use rust_music_theory::note::Note;
+use serde::Serialize;
+
+/// The notes shown on the neck in one combination of pedals and levers
+#[derive(Debug, Clone, Serialize)]
+pub struct NeckDiagram {
+    /// The pedals and levers engaged; empty for the Open position
+    pub position: Vec<String>,
+    /// The position as it is titled on the diagram, e.g. "A & B"
+    pub name: String,
+    /// Every note found within the frets shown, string by string
+    pub notes: Vec<NeckPositions>,
+}
+
+impl NeckDiagram {
+    /// Find `notes` on the neck in one position, keeping the frets in `frets`
+    pub fn new(
+        guitar: &Guitar,
+        copedent: &Copedent,
+        position: &[String],
+        notes: &[Note],
+        frets: FretRange,
+    ) -> Result<Self, CopedentError> {
+        let notes = identify_notes_on_neck(guitar, copedent, position, notes, None)?
+            .into_iter()
+            .filter(|note| frets.contains(note.fret))
+            .collect();
+
+        Ok(Self {
+            position: position.to_vec(),
+            name: position_name(position),
+            notes,
+        })
+    }
+}
+
+/// A guitar and its neck diagrams in each position shown
+#[derive(Serialize)]
+pub struct NeckReport<'a> {
+    pub guitar: &'a Guitar,
+    /// The frets the diagrams cover, both ends included
+    pub frets: FretRange,
+    pub diagrams: Vec<NeckDiagram>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{copedent::ControlKind, test_support::e9};
+    use rust_music_theory::note::{NoteLetter, Pitch};
+
+    #[test]
+    fn test_neck_report_json() {
+        let guitar = e9();
+        let e = Note::new(Pitch::new(NoteLetter::E, 0), 0);
+        let diagram = NeckDiagram::new(
+            &guitar,
+            &Copedent::default(),
+            &["A".to_string(), "B".to_string()],
+            &[e],
+            FretRange { start: 0, end: 0 },
+        )
+        .unwrap();
+        let report = NeckReport {
+            guitar: &guitar,
+            frets: FretRange { start: 0, end: 0 },
+            diagrams: vec![diagram],
+        };
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["guitar"]["name"], "E9");
+        assert_eq!(json["guitar"]["tuning"][0]["pitch"], "F#");
+        assert_eq!(json["guitar"]["tuning"][0]["midi"], 66);
+        assert_eq!(json["frets"]["end"], 0);
+        assert_eq!(json["diagrams"][0]["name"], "A & B");
+        assert_eq!(json["diagrams"][0]["position"][1], "B");
+        // The open E strings, counted from 1
+        let strings: Vec<_> = json["diagrams"][0]["notes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|note| note["string"].as_u64().unwrap())
+            .collect();
+        assert_eq!(strings, [4, 8]);
+        assert_eq!(json["diagrams"][0]["notes"][0]["note_name"], "E");
+        assert_eq!(json["diagrams"][0]["notes"][0]["fret"], 0);
+    }
+
+    #[test]
+    fn test_copedent_json() {
+        let copedent = Copedent::default();
+        let json = serde_json::to_value(&copedent).unwrap();
+        assert_eq!(json["pedals"][0]["name"], "A");
+        assert_eq!(json["pedals"][0]["foot"], "left");
+        assert_eq!(json["pedals"][0]["changes"][0]["string"], 10);
+        assert_eq!(json["pedals"][0]["changes"][0]["semitone_change"], 2);
+        assert_eq!(json["levers"][0]["name"], "LKL");
+        assert_eq!(json["levers"][0]["knee"], "left");
+    }
+
+    #[test]
+    fn test_copedent_json_loads_back() {
+        let copedent = Copedent::from_toml_str(
+            r#"
+            name = "Split"
+
+            [[pedals]]
+            name = "A"
+            foot = "right"
+            changes = [{ string = 5, semitone_change = 2, label = "B→C#" }]
+
+            [[levers]]
+            name = "LKV"
+            kind = "vertical"
+            changes = [{ string = 6, semitone_change = -2, half_stop = -1 }]
+
+            [constraints]
+            allow_both_feet = true
+
+            [[overrides]]
+            controls = ["A", "LKV"]
+            string = 5
+            semitone_change = 0
+            "#,
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&copedent).unwrap();
+        let loaded = Copedent::from_json_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        assert_eq!(loaded.name, "Split");
+        assert_eq!(loaded.controls.len(), 2);
+        assert_eq!(
+            loaded.control("LKV").unwrap().kind,
+            ControlKind::VerticalLever
+        );
+        assert_eq!(loaded.overrides, copedent.overrides);
+        assert_eq!(loaded.constraints, copedent.constraints);
+    }
+}
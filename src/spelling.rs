@@ -0,0 +1,491 @@
+//! Note spelling — parsing note names the way players write them.
+//!
+//! A note name is a letter followed by up to a double sharp or double flat.
+//! Sharps can be written `#` or `♯`, flats `b` or `♭` (in any case, so "AB" is
+//! A flat), and `x`/`𝄪` and `𝄫` are double sharps and double flats. German `H`
+//! is accepted for B natural. The parsed `Pitch` keeps the spelling, so Cb, E#
+//! or F## print back the way they were written.
+//!
+//! Scale and chord notes are spelled from their root: every note is named after
+//! the scale degree it plays, so C# major has an E# and a B# and a Bb
+//! diminished seventh has an Abb. A degree that would need a triple sharp or
+//! flat is written as its enharmonic instead, so every name printed can be
+//! read back.
+//!
+//! Going the other way, [`Interval`] reads how a spelled note relates to a root,
+//! for labelling notes as degrees ("b7"), intervals ("m7") or Nashville numbers.
+
+use rust_music_theory::{
+    chord::{Chord, Quality},
+    note::{Note, NoteLetter, Notes, Pitch},
+    scale::Scale,
+};
+use serde::Serializer;
+
+/// The most a note name can raise or lower its letter: a double sharp or flat
+const MAX_ACCIDENTAL: i8 = 2;
+
+/// Note letters in order, so a degree can be counted up from the root
+const LETTERS: [NoteLetter; 7] = [
+    NoteLetter::C,
+    NoteLetter::D,
+    NoteLetter::E,
+    NoteLetter::F,
+    NoteLetter::G,
+    NoteLetter::A,
+    NoteLetter::B,
+];
+
+/// Semitones above the root of each degree of the major scale
+pub(crate) const MAJOR_SCALE: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// How a note relates to a root: the degree it is spelled as (1 for the root, 3
+/// for the third, 9 for the ninth) and how many semitones it is above the root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub degree: u8,
+    pub semitones: u8,
+}
+
+impl Interval {
+    /// The interval from `root` up to `note`, read from their spelling: C to E
+    /// is a third and C to Fb is a diminished fourth
+    pub fn between(root: Pitch, note: Pitch) -> Self {
+        let degree = (letter_index(note) + 7 - letter_index(root)) % 7 + 1;
+        Self {
+            degree: u8::try_from(degree).unwrap_or(1),
+            semitones: (note.into_u8() + 12 - root.into_u8()) % 12,
+        }
+    }
+
+    /// How far the note is raised (positive) or lowered (negative) from the
+    /// same degree of the major scale
+    fn alteration(&self) -> i8 {
+        let major = MAJOR_SCALE[usize::from(self.degree.saturating_sub(1) % 7)];
+        let alteration = (i16::from(self.semitones) - i16::from(major) + 6).rem_euclid(12) - 6;
+        i8::try_from(alteration).unwrap_or_default()
+    }
+
+    /// Sharps or flats for the alteration, e.g. "b" for a minor third
+    fn accidentals(&self) -> String {
+        let alteration = self.alteration();
+        let symbol = if alteration < 0 { "b" } else { "#" };
+        symbol.repeat(usize::from(alteration.unsigned_abs()))
+    }
+
+    /// The degree as players write it: "R" for the root, then "b3", "5", "b7", "9"
+    pub fn degree_name(&self) -> String {
+        if self.degree == 1 && self.alteration() == 0 {
+            "R".to_string()
+        } else {
+            format!("{}{}", self.accidentals(), self.degree)
+        }
+    }
+
+    /// The Nashville number, counting the degree within one octave: "1", "b3", "2"
+    pub fn nashville(&self) -> String {
+        format!("{}{}", self.accidentals(), (self.degree - 1) % 7 + 1)
+    }
+
+    /// The interval name: perfect, major, minor, augmented or diminished, then
+    /// the degree, e.g. "P5", "m3", "A4" or "M9"
+    pub fn name(&self) -> String {
+        let perfect = matches!((self.degree - 1) % 7, 0 | 3 | 4);
+        let quality = match (perfect, self.alteration()) {
+            (true, 0) => "P".to_string(),
+            (false, 0) => "M".to_string(),
+            (false, -1) => "m".to_string(),
+            (_, alteration) if alteration > 0 => "A".repeat(alteration.unsigned_abs().into()),
+            (true, alteration) => "d".repeat(alteration.unsigned_abs().into()),
+            (false, alteration) => "d".repeat((alteration.unsigned_abs() - 1).into()),
+        };
+        format!("{quality}{}", self.degree)
+    }
+}
+
+/// Position of the note's letter in [`LETTERS`]
+fn letter_index(pitch: Pitch) -> usize {
+    LETTERS.iter().position(|&l| l == pitch.letter).unwrap_or(0)
+}
+
+/// The ways to spell a pitch class with at most one sharp or flat, e.g. C# and
+/// Db for 1, or F and E# for 5
+pub fn spellings(pitch_class: u8) -> Vec<Pitch> {
+    LETTERS
+        .iter()
+        .flat_map(|&letter| (-1..=1).map(move |accidental| Pitch::new(letter, accidental)))
+        .filter(|pitch| pitch.into_u8() == pitch_class % 12)
+        .collect()
+}
+
+/// Spell the note a string reaches when a pedal or lever moves it `semitones`
+/// from `open`: the spelling with the fewest accidentals, and on a tie the one
+/// players write, where a half step keeps the letter (E→Eb) and a whole step
+/// moves to the next letter (G#→F#)
+pub fn spell_change(open: Pitch, semitones: i8) -> Pitch {
+    let pitch_class = (i16::from(open.into_u8()) + i16::from(semitones)).rem_euclid(12);
+    let expected_steps = i16::from(semitones / 2);
+    let steps = |pitch: Pitch| {
+        // Letters moved, from -3 to 3
+        let steps = (letter_index(pitch) + 7 - letter_index(open)) % 7;
+        i16::try_from(steps).unwrap_or_default() - if steps > 3 { 7 } else { 0 }
+    };
+    spellings(u8::try_from(pitch_class).unwrap_or_default())
+        .into_iter()
+        .min_by_key(|&pitch| {
+            (
+                pitch.accidental.unsigned_abs(),
+                (steps(pitch) - expected_steps).unsigned_abs(),
+            )
+        })
+        .unwrap_or(open)
+}
+
+/// Parse a note name such as "F#", "Cb", "E♯", "Gx", "Bbb" or "H".
+/// Sharps and flats cannot be mixed in one name, and nothing past a double
+/// sharp or double flat is accepted.
+pub fn parse_note_name(name: &str) -> Option<Pitch> {
+    let mut chars = name.chars();
+    let letter = match chars.next()?.to_ascii_uppercase() {
+        'C' => NoteLetter::C,
+        'D' => NoteLetter::D,
+        'E' => NoteLetter::E,
+        'F' => NoteLetter::F,
+        'G' => NoteLetter::G,
+        'A' => NoteLetter::A,
+        'B' | 'H' => NoteLetter::B,
+        _ => return None,
+    };
+
+    let mut accidental: i8 = 0;
+    let (mut sharps, mut flats) = (false, false);
+    for c in chars {
+        let step = match c {
+            '#' | '♯' => 1,
+            'x' | 'X' | '𝄪' => 2,
+            'b' | 'B' | '♭' => -1,
+            '𝄫' => -2,
+            _ => return None,
+        };
+        sharps |= step > 0;
+        flats |= step < 0;
+        accidental += step;
+        if accidental.abs() > MAX_ACCIDENTAL {
+            return None;
+        }
+    }
+    if sharps && flats {
+        return None;
+    }
+
+    Some(Pitch::new(letter, accidental))
+}
+
+/// Write a note name the way it prints, e.g. "G#" or "Bb", for fields
+/// serialized with `#[serde(serialize_with = "serialize_pitch")]`
+pub(crate) fn serialize_pitch<S: Serializer>(
+    pitch: &Pitch,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(pitch)
+}
+
+/// Spell the note `semitones` above `root` as the given scale degree, where 1
+/// is the root, 3 the third, 9 the ninth and so on. Past a double sharp or
+/// flat the plainest enharmonic is used instead.
+pub fn spell_degree(root: Pitch, semitones: u8, degree: u8) -> Pitch {
+    let letter = LETTERS[(letter_index(root) + usize::from(degree.saturating_sub(1))) % 7];
+
+    // How far the plain letter is from the plain root letter, and what it takes to get
+    // from there to the note
+    let natural = |letter| i16::from(Pitch::new(letter, 0).into_u8());
+    let letter_distance = (natural(letter) - natural(root.letter)).rem_euclid(12);
+    let wanted = (i16::from(semitones) + i16::from(root.accidental)).rem_euclid(12);
+    let accidental = (wanted - letter_distance + 6).rem_euclid(12) - 6;
+
+    let pitch = Pitch::new(letter, i8::try_from(accidental).unwrap_or_default());
+    if pitch.accidental.abs() <= MAX_ACCIDENTAL {
+        return pitch;
+    }
+    // Keep as close to the degree's letter as the enharmonics allow
+    let letter_steps = |spelling: Pitch| {
+        let steps = (letter_index(spelling) + 7 - letter_index(pitch)) % 7;
+        steps.min(7 - steps)
+    };
+    spellings(pitch.into_u8())
+        .into_iter()
+        .min_by_key(|&spelling| (spelling.accidental.unsigned_abs(), letter_steps(spelling)))
+        .unwrap_or(pitch)
+}
+
+/// The degree a note usually plays in a scale, from its distance to the tonic.
+/// Seven-note scales use each letter once instead.
+fn scale_degree(semitones: u8, scale: &[u8]) -> u8 {
+    match semitones {
+        0 => 1,
+        1 | 2 => 2,
+        3 | 4 => 3,
+        5 => 4,
+        // A tritone is a raised fourth unless the scale already has a fourth
+        6 if !scale.contains(&5) => 4,
+        6 | 7 => 5,
+        // Likewise a raised fifth unless the scale already has a fifth
+        8 if !scale.contains(&7) => 5,
+        8 | 9 => 6,
+        _ => 7,
+    }
+}
+
+/// The degree a chord tone plays, from its distance to the root
+fn chord_degree(semitones: u8, chord: &Chord) -> u8 {
+    match (semitones, chord.quality) {
+        (0, _) => 1,
+        (2, Quality::Suspended2) => 2,
+        (5, Quality::Suspended4) => 4,
+        (1 | 2, _) => 9,
+        (3 | 4, _) => 3,
+        (5, _) => 11,
+        (6..=8, _) => 5,
+        // The diminished seventh is a doubly flattened seventh, not a sixth
+        (9, Quality::Diminished) if chord.intervals.len() > 2 => 7,
+        (9, _) => 13,
+        _ => 7,
+    }
+}
+
+/// Spell a list of notes from a root, giving each note the degree `degree` picks
+fn spell_notes(root: Pitch, notes: Vec<Note>, degree: impl Fn(u8) -> u8) -> Vec<Note> {
+    notes
+        .into_iter()
+        .map(|note| {
+            let semitones = (note.pitch.into_u8() + 12 - root.into_u8()) % 12;
+            Note::new(
+                spell_degree(root, semitones, degree(semitones)),
+                note.octave,
+            )
+        })
+        .collect()
+}
+
+/// The notes of a scale, spelled from its tonic
+pub fn scale_notes(scale: &Scale) -> Vec<Note> {
+    let notes = scale.notes();
+    let mut semitones: Vec<u8> = notes
+        .iter()
+        .map(|note| (note.pitch.into_u8() + 12 - scale.tonic.into_u8()) % 12)
+        .collect();
+    semitones.sort_unstable();
+    semitones.dedup();
+
+    spell_notes(scale.tonic, notes, |distance| {
+        if semitones.len() == 7 {
+            let index = semitones.iter().position(|&s| s == distance).unwrap_or(0);
+            u8::try_from(index + 1).unwrap_or(1)
+        } else {
+            scale_degree(distance, &semitones)
+        }
+    })
+}
+
+/// The notes of a chord, spelled from its root as stacked thirds
+pub fn chord_notes(chord: &Chord) -> Vec<Note> {
+    spell_notes(chord.root, chord.notes(), |distance| {
+        chord_degree(distance, chord)
+    })
+}
+
+/// The interval from the root of a chord to one of its notes, naming the
+/// extensions above the octave, so the second of a ninth chord is a 9
+pub fn chord_interval(chord: &Chord, note: Pitch) -> Interval {
+    let mut interval = Interval::between(chord.root, note);
+    if chord_degree(interval.semitones, chord) > 7 {
+        interval.degree += 7;
+    }
+    interval
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::names;
+
+    #[test]
+    fn test_parse_note_name_round_trips() {
+        for name in [
+            "C", "F#", "Bb", "Cb", "Fb", "E#", "B#", "F##", "Bbb", "Gbb", "C##",
+        ] {
+            let pitch = parse_note_name(name).unwrap();
+            assert_eq!(pitch.to_string(), name);
+        }
+    }
+
+    #[test]
+    fn test_parse_note_name_symbols() {
+        assert_eq!(parse_note_name("f♯"), parse_note_name("F#"));
+        assert_eq!(parse_note_name("E♭"), parse_note_name("Eb"));
+        assert_eq!(parse_note_name("Fx"), parse_note_name("F##"));
+        assert_eq!(parse_note_name("F𝄪"), parse_note_name("F##"));
+        assert_eq!(parse_note_name("B𝄫"), parse_note_name("Bbb"));
+        assert_eq!(parse_note_name("H"), parse_note_name("B"));
+        assert_eq!(parse_note_name("bb"), parse_note_name("Bb"));
+    }
+
+    #[test]
+    fn test_parse_note_name_pitch_classes() {
+        assert_eq!(parse_note_name("Cb").unwrap().into_u8(), 11);
+        assert_eq!(parse_note_name("B#").unwrap().into_u8(), 0);
+        assert_eq!(parse_note_name("E#").unwrap().into_u8(), 5);
+        assert_eq!(parse_note_name("Fb").unwrap().into_u8(), 4);
+    }
+
+    #[test]
+    fn test_parse_note_name_rejects() {
+        for name in [
+            "",
+            "X",
+            "P Don Helms",
+            "F#b",
+            "E s",
+            "Cm",
+            "Gbbb",
+            "C###",
+            "Fx#",
+        ] {
+            assert!(parse_note_name(name).is_none(), "{name}");
+        }
+        // Long runs of accidentals are rejected rather than wrapping around
+        assert!(parse_note_name(&format!("B{}", "x".repeat(60))).is_none());
+        assert!(parse_note_name(&format!("C{}", "b".repeat(13))).is_none());
+    }
+
+    #[test]
+    fn test_spell_degree() {
+        let c_sharp = parse_note_name("C#").unwrap();
+        assert_eq!(spell_degree(c_sharp, 4, 3).to_string(), "E#");
+        assert_eq!(spell_degree(c_sharp, 11, 7).to_string(), "B#");
+        let b_flat = parse_note_name("Bb").unwrap();
+        assert_eq!(spell_degree(b_flat, 9, 7).to_string(), "Abb");
+        // The diminished seventh of Fb would be Ebbb
+        let f_flat = parse_note_name("Fb").unwrap();
+        assert_eq!(spell_degree(f_flat, 9, 7).to_string(), "Db");
+    }
+
+    #[test]
+    fn test_spell_degree_round_trips() {
+        for letter in LETTERS {
+            for accidental in -MAX_ACCIDENTAL..=MAX_ACCIDENTAL {
+                let root = Pitch::new(letter, accidental);
+                for semitones in 0..12 {
+                    for degree in 1..=13 {
+                        let pitch = spell_degree(root, semitones, degree);
+                        let name = pitch.to_string();
+                        assert_eq!(parse_note_name(&name), Some(pitch), "{root} {degree}");
+                        assert_eq!(
+                            pitch.into_u8(),
+                            (root.into_u8() + semitones) % 12,
+                            "{root} {degree}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_scale_notes_use_each_letter_once() {
+        let scale = Scale::from_regex("C# major").unwrap();
+        assert_eq!(
+            names(&scale_notes(&scale)),
+            ["C#", "D#", "E#", "F#", "G#", "A#", "B#", "C#"]
+        );
+
+        let scale = Scale::from_regex("Eb minor").unwrap();
+        assert_eq!(
+            names(&scale_notes(&scale)),
+            ["Eb", "F", "Gb", "Ab", "Bb", "Cb", "Db", "Eb"]
+        );
+    }
+
+    #[test]
+    fn test_scale_notes_without_seven_notes() {
+        let scale = Scale::from_regex("Bb blues").unwrap();
+        assert_eq!(
+            names(&scale_notes(&scale)),
+            ["Bb", "Db", "Eb", "Fb", "F", "Ab", "Bb"]
+        );
+
+        let scale = Scale::from_regex("C whole tone").unwrap();
+        assert_eq!(
+            names(&scale_notes(&scale)),
+            ["C", "D", "E", "F#", "G#", "Bb", "C"]
+        );
+    }
+
+    #[test]
+    fn test_chord_notes() {
+        let spell = |chord: &str| names(&chord_notes(&Chord::from_regex(chord).unwrap()));
+        assert_eq!(spell("C# major"), ["C#", "E#", "G#"]);
+        assert_eq!(spell("Eb minor"), ["Eb", "Gb", "Bb"]);
+        assert_eq!(spell("A augmented triad"), ["A", "C#", "E#"]);
+        assert_eq!(spell("Bb diminished seventh"), ["Bb", "Db", "Fb", "Abb"]);
+        assert_eq!(spell("Gb dominant seventh"), ["Gb", "Bb", "Db", "Fb"]);
+        assert_eq!(spell("D suspended4 triad"), ["D", "G", "A"]);
+    }
+
+    #[test]
+    fn test_interval_between() {
+        let pitch = |name| parse_note_name(name).unwrap();
+        let label = |root, note| {
+            let interval = Interval::between(pitch(root), pitch(note));
+            (
+                interval.degree_name(),
+                interval.name(),
+                interval.nashville(),
+            )
+        };
+        let strings = |a: &str, b: &str, c: &str| (a.to_string(), b.to_string(), c.to_string());
+        assert_eq!(label("E", "E"), strings("R", "P1", "1"));
+        assert_eq!(label("E", "G"), strings("b3", "m3", "b3"));
+        assert_eq!(label("E", "G#"), strings("3", "M3", "3"));
+        assert_eq!(label("E", "A#"), strings("#4", "A4", "#4"));
+        assert_eq!(label("E", "Bb"), strings("b5", "d5", "b5"));
+        assert_eq!(label("E", "D"), strings("b7", "m7", "b7"));
+        assert_eq!(label("Bb", "Abb"), strings("bb7", "d7", "bb7"));
+        assert_eq!(label("C", "Fb"), strings("b4", "d4", "b4"));
+    }
+
+    #[test]
+    fn test_spell_change() {
+        let change = |open: &str, semitones: i8| {
+            spell_change(parse_note_name(open).unwrap(), semitones).to_string()
+        };
+        assert_eq!(change("B", 2), "C#");
+        assert_eq!(change("G#", 1), "A");
+        assert_eq!(change("E", 1), "F");
+        assert_eq!(change("E", -1), "Eb");
+        assert_eq!(change("B", -1), "Bb");
+        assert_eq!(change("G#", -2), "F#");
+        assert_eq!(change("D#", -1), "D");
+        assert_eq!(change("F#", 2), "G#");
+    }
+
+    #[test]
+    fn test_chord_interval_names_extensions() {
+        let chord = Chord::from_regex("E dominant ninth").unwrap();
+        let degrees: Vec<String> = chord_notes(&chord)
+            .iter()
+            .map(|note| chord_interval(&chord, note.pitch).degree_name())
+            .collect();
+        assert_eq!(degrees, ["R", "3", "5", "b7", "9"]);
+        assert_eq!(
+            chord_interval(&chord, parse_note_name("F#").unwrap()).name(),
+            "M9"
+        );
+
+        let chord = Chord::from_regex("D suspended2 triad").unwrap();
+        assert_eq!(
+            chord_interval(&chord, parse_note_name("E").unwrap()).degree_name(),
+            "2"
+        );
+    }
+}
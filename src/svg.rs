@@ -0,0 +1,321 @@
+//! SVG neck diagrams — printable fretboard drawings.
+//!
+//! Draws the notes of the text neck diagrams as a fretboard: strings, fret
+//! wires spaced as on the guitar, inlay dots and a labelled circle for every
+//! note, with the combination of pedals and levers as the title. A steel is
+//! played over the fret wire rather than behind it, so the notes sit on the
+//! wires and open strings on the nut. The drawing uses no external fonts or
+//! stylesheets, so it prints the same anywhere.
+
+use crate::{
+    guitar::{FretRange, Guitar, NeckPositions, inlay_dots},
+    report::NeckDiagram,
+};
+use std::fmt::Write;
+
+/// Space between neighbouring strings
+const STRING_SPACING: f64 = 24.0;
+/// Narrowest gap between two fret wires, wide enough for a note circle
+const MIN_FRET_SPACING: f64 = 30.0;
+/// Radius of a note circle
+const NOTE_RADIUS: f64 = 10.0;
+/// Radius of an inlay dot
+const INLAY_RADIUS: f64 = 5.0;
+/// Room left of the fretboard for the string numbers
+const LEFT_MARGIN: f64 = 40.0;
+/// Room right of and below each fretboard
+const MARGIN: f64 = 20.0;
+/// Height of the guitar name at the top of the sheet
+const HEADING_HEIGHT: f64 = 32.0;
+/// Height of the title above each fretboard
+const TITLE_HEIGHT: f64 = 28.0;
+/// Height of the fret numbers below each fretboard
+const FRET_NUMBER_HEIGHT: f64 = 24.0;
+
+/// Escape text for use in SVG or HTML
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Where the wire of each fret shown is drawn, measured from the left of the
+/// drawing. The real spacing is scaled so the narrowest gap still fits a note.
+pub(crate) fn fret_positions(guitar: &Guitar, frets: FretRange) -> Vec<f64> {
+    let narrowest = match frets.end {
+        0 => 1.0,
+        end => guitar.fret_distance(end) - guitar.fret_distance(end - 1),
+    };
+    let scale = MIN_FRET_SPACING / narrowest;
+    let first = guitar.fret_distance(frets.start);
+    frets
+        .frets()
+        .map(|fret| LEFT_MARGIN + NOTE_RADIUS + (guitar.fret_distance(fret) - first) * scale)
+        .collect()
+}
+
+/// Width of a fretboard drawing
+fn neck_width(guitar: &Guitar, frets: FretRange) -> f64 {
+    let last = fret_positions(guitar, frets).last().copied().unwrap_or(0.0);
+    last + NOTE_RADIUS + MARGIN
+}
+
+/// Height of a fretboard drawing, with its title and fret numbers
+fn neck_height(guitar: &Guitar) -> f64 {
+    let strings = guitar.tuning.len().saturating_sub(1) as f64;
+    TITLE_HEIGHT + 2.0 * NOTE_RADIUS + strings * STRING_SPACING + FRET_NUMBER_HEIGHT + MARGIN
+}
+
+/// Draw one fretboard with its notes, `top` pixels from the top of the drawing.
+/// With `hover`, every note carries a tooltip.
+fn draw_neck(
+    svg: &mut String,
+    guitar: &Guitar,
+    diagram: &NeckDiagram,
+    label: &dyn Fn(&NeckPositions) -> String,
+    hover: Option<&dyn Fn(&NeckPositions) -> String>,
+    frets: FretRange,
+    top: f64,
+) {
+    let wires = fret_positions(guitar, frets);
+    let x = |fret: usize| wires[fret - frets.start];
+    let first_string = top + TITLE_HEIGHT + NOTE_RADIUS;
+    let y = |string: usize| first_string + string as f64 * STRING_SPACING;
+    let last_string = y(guitar.tuning.len().saturating_sub(1));
+    let (left, right) = (x(frets.start) - NOTE_RADIUS, x(frets.end) + NOTE_RADIUS);
+
+    writeln!(
+        svg,
+        r#"<text x="{LEFT_MARGIN}" y="{:.1}" font-size="16" font-weight="bold">{}</text>"#,
+        top + 18.0,
+        escape(&diagram.name)
+    )
+    .ok();
+    writeln!(
+        svg,
+        r##"<rect x="{left:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#f5efe0"/>"##,
+        first_string - NOTE_RADIUS,
+        right - left,
+        last_string - first_string + 2.0 * NOTE_RADIUS
+    )
+    .ok();
+
+    // Inlays sit between the strings: one dot in the middle, two at the octaves
+    let middle = (first_string + last_string) / 2.0;
+    let quarter = (last_string - first_string) / 4.0;
+    for fret in frets.frets() {
+        let dots: &[f64] = match inlay_dots(fret) {
+            2 => &[middle - quarter, middle + quarter],
+            1 => &[middle],
+            _ => &[],
+        };
+        for dot in dots {
+            writeln!(
+                svg,
+                r##"<circle cx="{:.1}" cy="{dot:.1}" r="{INLAY_RADIUS}" fill="#c8bfae"/>"##,
+                x(fret)
+            )
+            .ok();
+        }
+    }
+
+    // Fret wires, with a heavy line for the nut
+    for fret in frets.frets() {
+        let width = if fret == 0 { 4 } else { 1 };
+        writeln!(
+            svg,
+            r##"<line x1="{0:.1}" y1="{1:.1}" x2="{0:.1}" y2="{2:.1}" stroke="#8a8a8a" stroke-width="{width}"/>"##,
+            x(fret),
+            first_string - NOTE_RADIUS,
+            last_string + NOTE_RADIUS
+        )
+        .ok();
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" font-size="11" text-anchor="middle">{fret}</text>"#,
+            x(fret),
+            last_string + NOTE_RADIUS + 16.0
+        )
+        .ok();
+    }
+
+    // Strings, getting heavier towards the bass, numbered from 1
+    for string in 0..guitar.tuning.len() {
+        writeln!(
+            svg,
+            r##"<line x1="{left:.1}" y1="{0:.1}" x2="{right:.1}" y2="{0:.1}" stroke="#444" stroke-width="{1:.2}"/>"##,
+            y(string),
+            1.0 + string as f64 * 0.15
+        )
+        .ok();
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" font-size="11" text-anchor="end">{}</text>"#,
+            LEFT_MARGIN - 8.0,
+            y(string) + 4.0,
+            string + 1
+        )
+        .ok();
+    }
+
+    for note in diagram
+        .notes
+        .iter()
+        .filter(|note| frets.contains(note.fret))
+    {
+        let text = label(note);
+        let size = if text.chars().count() > 2 { 8 } else { 10 };
+        if let Some(hover) = hover {
+            writeln!(svg, "<g><title>{}</title>", escape(&hover(note))).ok();
+        }
+        writeln!(
+            svg,
+            r##"<circle cx="{:.1}" cy="{:.1}" r="{NOTE_RADIUS}" fill="#fff" stroke="#222"/>"##,
+            x(note.fret),
+            y(note.string)
+        )
+        .ok();
+        writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" font-size="{size}" text-anchor="middle">{}</text>"#,
+            x(note.fret),
+            y(note.string) + f64::from(size) / 3.0,
+            escape(&text)
+        )
+        .ok();
+        if hover.is_some() {
+            svg.push_str("</g>\n");
+        }
+    }
+}
+
+/// Start an SVG drawing with a white background
+fn open_svg(width: f64, height: f64) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}" font-family="sans-serif">"#
+    )
+    .ok();
+    writeln!(svg, r##"<rect width="100%" height="100%" fill="#fff"/>"##).ok();
+    svg
+}
+
+/// Draw neck diagrams one under the other on a single sheet, headed by the
+/// name of the guitar. Pass one diagram for a drawing of one combination.
+pub fn neck_sheet(
+    guitar: &Guitar,
+    diagrams: &[NeckDiagram],
+    frets: FretRange,
+    label: &dyn Fn(&NeckPositions) -> String,
+) -> String {
+    let heading = if guitar.name.is_empty() {
+        0.0
+    } else {
+        HEADING_HEIGHT
+    };
+    let width = neck_width(guitar, frets);
+    let height = heading + diagrams.len() as f64 * neck_height(guitar);
+
+    let mut svg = open_svg(width, height);
+    if !guitar.name.is_empty() {
+        writeln!(
+            svg,
+            r#"<text x="{LEFT_MARGIN}" y="22" font-size="20" font-weight="bold">{}</text>"#,
+            escape(&guitar.name)
+        )
+        .ok();
+    }
+    for (i, diagram) in diagrams.iter().enumerate() {
+        let top = heading + i as f64 * neck_height(guitar);
+        draw_neck(&mut svg, guitar, diagram, label, None, frets, top);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Draw one fretboard on its own, without the name of the guitar, and show
+/// `hover` when the pointer rests on a note
+pub fn neck_drawing(
+    guitar: &Guitar,
+    diagram: &NeckDiagram,
+    frets: FretRange,
+    label: &dyn Fn(&NeckPositions) -> String,
+    hover: &dyn Fn(&NeckPositions) -> String,
+) -> String {
+    let mut svg = open_svg(neck_width(guitar, frets), neck_height(guitar));
+    draw_neck(&mut svg, guitar, diagram, label, Some(hover), frets, 0.0);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A file name for the drawing of one combination, e.g. "a-b.svg" for A and B
+pub fn file_name(diagram: &NeckDiagram) -> String {
+    let name: String = diagram
+        .name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("{name}.svg")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{copedent::Copedent, test_support::e9};
+    use rust_music_theory::note::{Note, NoteLetter, Pitch};
+
+    fn diagram(guitar: &Guitar, position: &[&str], frets: FretRange) -> NeckDiagram {
+        let position: Vec<String> = position.iter().map(ToString::to_string).collect();
+        let e = Note::new(Pitch::new(NoteLetter::E, 0), 0);
+        NeckDiagram::new(guitar, &Copedent::default(), &position, &[e], frets).unwrap()
+    }
+
+    #[test]
+    fn test_fret_positions_narrow_up_the_neck() {
+        let guitar = e9();
+        let wires = fret_positions(&guitar, FretRange::first_twelve());
+        assert_eq!(wires.len(), 12);
+        let gaps: Vec<f64> = wires.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(gaps.windows(2).all(|pair| pair[1] < pair[0]));
+        assert!((gaps[10] - MIN_FRET_SPACING).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_neck_sheet_draws_every_note() {
+        let guitar = e9();
+        let frets = FretRange::first_twelve();
+        let diagrams = [
+            diagram(&guitar, &[], frets),
+            diagram(&guitar, &["A", "B"], frets),
+        ];
+        let svg = neck_sheet(&guitar, &diagrams, frets, &|note| note.note_name.clone());
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">Open</text>"));
+        assert!(svg.contains(">A &amp; B</text>"));
+        // One inlay at each of frets 3, 5, 7 and 9; the octave is past fret 11
+        let notes: usize = diagrams.iter().map(|diagram| diagram.notes.len()).sum();
+        assert_eq!(svg.matches("<circle").count(), notes + 2 * 4);
+    }
+
+    #[test]
+    fn test_file_name() {
+        let guitar = e9();
+        let frets = FretRange::first_twelve();
+        assert_eq!(file_name(&diagram(&guitar, &[], frets)), "open.svg");
+        assert_eq!(file_name(&diagram(&guitar, &["A", "B"], frets)), "a-b.svg");
+        assert_eq!(file_name(&diagram(&guitar, &["LKL"], frets)), "lkl.svg");
+    }
+}
@@ -0,0 +1,15 @@
+//! Fixtures shared by the unit tests.
+
+use crate::{guitar::Guitar, presets};
+
+/// A ten-string E9 guitar, from the built-in preset
+pub(crate) fn e9() -> Guitar {
+    let preset = presets::tuning("E9").expect("the E9 preset exists");
+    Guitar::new("E9", preset.notes).expect("the E9 preset parses")
+}
+
+/// Each item written out, e.g. control names or chords for comparing in an
+/// assertion
+pub(crate) fn names<T: ToString>(items: &[T]) -> Vec<String> {
+    items.iter().map(ToString::to_string).collect()
+}
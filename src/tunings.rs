@@ -1,39 +1,182 @@
 //! Tuning helpers for pedal-steel CLI.
 //!
-//! Contains functions to parse tuning strings into vectors of Pitch objects.
-
-use rust_music_theory::note::{NoteLetter, Pitch};
-
-/// Parse a comma-separated string of note names into a vector of Pitch objects
-pub fn tuning(notes: &str) -> Vec<Pitch> {
-    let mut pitches = Vec::new();
-
-    for raw_note in notes.split(',') {
-        let note = raw_note.trim().to_ascii_uppercase();
-
-        match note.as_str() {
-            "AB" => pitches.push(Pitch::new(NoteLetter::A, -1)),
-            "A" => pitches.push(Pitch::new(NoteLetter::A, 0)),
-            "A#" => pitches.push(Pitch::new(NoteLetter::A, 1)),
-            "BB" => pitches.push(Pitch::new(NoteLetter::B, -1)),
-            "B" => pitches.push(Pitch::new(NoteLetter::B, 0)),
-            "C" => pitches.push(Pitch::new(NoteLetter::C, 0)),
-            "C#" => pitches.push(Pitch::new(NoteLetter::C, 1)),
-            "DB" => pitches.push(Pitch::new(NoteLetter::D, -1)),
-            "D" => pitches.push(Pitch::new(NoteLetter::D, 0)),
-            "D#" => pitches.push(Pitch::new(NoteLetter::D, 1)),
-            "EB" => pitches.push(Pitch::new(NoteLetter::E, -1)),
-            "E" => pitches.push(Pitch::new(NoteLetter::E, 0)),
-            "F" => pitches.push(Pitch::new(NoteLetter::F, 0)),
-            "F#" => pitches.push(Pitch::new(NoteLetter::F, 1)),
-            "GB" => pitches.push(Pitch::new(NoteLetter::G, -1)),
-            "G" => pitches.push(Pitch::new(NoteLetter::G, 0)),
-            "G#" => pitches.push(Pitch::new(NoteLetter::G, 1)),
-            _ => (),
+//! Contains functions to parse tuning strings into vectors of Pitch objects and
+//! to place those pitches in their sounding octave. Notes may carry an octave in
+//! scientific pitch notation ("G#4") or be given as MIDI note numbers ("68").
+
+use crate::spelling::{parse_note_name, serialize_pitch};
+use rust_music_theory::note::Pitch;
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// Errors that can occur while parsing a tuning or a list of notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TuningError {
+    /// No notes were given
+    Empty,
+    /// A token is not a note name. `index` counts from 0 in the comma-separated list.
+    InvalidNote { token: String, index: usize },
+}
+
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no notes given"),
+            Self::InvalidNote { token, index } => {
+                write!(f, "{token:?} at index {index} is not a note name")
+            }
         }
     }
+}
+
+impl std::error::Error for TuningError {}
+
+/// A note at a definite octave: how it is spelled and the MIDI note it sounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct AbsolutePitch {
+    #[serde(serialize_with = "serialize_pitch")]
+    pub pitch: Pitch,
+    /// Sounding pitch as a MIDI note number
+    pub midi: u8,
+}
+
+impl AbsolutePitch {
+    /// The octave in scientific pitch notation, following the spelling: B#3 and
+    /// C4 sound the same but are in different octaves
+    pub fn octave(&self) -> i8 {
+        let natural = i16::from(Pitch::new(self.pitch.letter, 0).into_u8());
+        let octave =
+            (i16::from(self.midi) - i16::from(self.pitch.accidental) - natural).div_euclid(12) - 1;
+        i8::try_from(octave).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for AbsolutePitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.pitch, self.octave())
+    }
+}
+
+/// A tuning: the sounding pitch of each open string, string 1 first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    pub strings: Vec<AbsolutePitch>,
+}
+
+impl Tuning {
+    /// The pitch of each string without its octave
+    pub fn pitches(&self) -> Vec<Pitch> {
+        self.strings.iter().map(|string| string.pitch).collect()
+    }
+}
+
+impl FromStr for Tuning {
+    type Err = TuningError;
+
+    /// Parse a comma-separated tuning. Strings given without an octave are
+    /// placed next to the string before them, as in [`midi_tuning`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut previous = None;
+        let strings = tokens(s)?
+            .map(|(index, token)| {
+                let (pitch, midi) = parse_token(index, token)?;
+                let midi = midi.unwrap_or_else(|| nearest_midi(previous, &pitch));
+                previous = Some(midi);
+                Ok(AbsolutePitch { pitch, midi })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { strings })
+    }
+}
+
+/// Split a comma-separated note list into trimmed tokens and their index
+fn tokens(notes: &str) -> Result<impl Iterator<Item = (usize, &str)>, TuningError> {
+    if notes.trim().is_empty() {
+        return Err(TuningError::Empty);
+    }
+    Ok(notes.split(',').map(str::trim).enumerate())
+}
+
+/// Parse a note such as "F#", "F#4" or the MIDI number "66", returning its
+/// pitch and, when the octave is given, its MIDI note number
+fn parse_token(index: usize, token: &str) -> Result<(Pitch, Option<u8>), TuningError> {
+    let invalid = || TuningError::InvalidNote {
+        token: token.to_string(),
+        index,
+    };
+
+    if !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit()) {
+        let midi = token.parse::<u8>().ok().filter(|&midi| midi <= 127);
+        return midi
+            .map(|midi| (Pitch::from_u8(midi % 12), Some(midi)))
+            .ok_or_else(invalid);
+    }
+
+    let name = token.trim_end_matches(|c: char| c.is_ascii_digit());
+    let pitch = parse_note_name(name).ok_or_else(invalid)?;
+    if name.len() == token.len() {
+        return Ok((pitch, None));
+    }
+
+    let octave: i16 = token[name.len()..].parse().map_err(|_| invalid())?;
+    let natural = i16::from(Pitch::new(pitch.letter, 0).into_u8());
+    let midi = (octave + 1) * 12 + natural + i16::from(pitch.accidental);
+    let midi = u8::try_from(midi)
+        .ok()
+        .filter(|&midi| midi <= 127)
+        .ok_or_else(invalid)?;
+    Ok((pitch, Some(midi)))
+}
+
+/// Parse a comma-separated string of note names into a vector of Pitch objects.
+/// Octaves and MIDI numbers are accepted and dropped. Fails on the first token
+/// that is not a note.
+pub fn tuning(notes: &str) -> Result<Vec<Pitch>, TuningError> {
+    tokens(notes)?
+        .map(|(index, token)| parse_token(index, token).map(|(pitch, _)| pitch))
+        .collect()
+}
 
+/// MIDI note number of C4 (middle C)
+const MIDI_C4: i16 = 60;
+
+/// Place each string of a tuning in its sounding octave and return the MIDI note
+/// numbers, string 1 first.
+///
+/// String 1 is placed in octave 4 and every following string is placed at the
+/// pitch nearest to the string before it. This matches how steel tunings are
+/// laid out (mostly descending, with the occasional higher string such as the
+/// third string of E9).
+pub fn midi_tuning(pitches: &[Pitch]) -> Vec<u8> {
+    let mut previous = None;
     pitches
+        .iter()
+        .map(|pitch| {
+            let midi = nearest_midi(previous, pitch);
+            previous = Some(midi);
+            midi
+        })
+        .collect()
+}
+
+/// The MIDI note of `pitch` nearest to the previous string, or in octave 4 for
+/// the first string
+fn nearest_midi(previous: Option<u8>, pitch: &Pitch) -> u8 {
+    let pitch_class = i16::from(pitch.into_u8());
+    let note = match previous.map(i16::from) {
+        None => MIDI_C4 + pitch_class,
+        Some(previous) => {
+            // Pick the octave closest to the previous string, preferring the lower
+            // note when the interval is a tritone either way
+            let above = previous + (pitch_class - previous).rem_euclid(12);
+            if above - previous < 6 {
+                above
+            } else {
+                above - 12
+            }
+        }
+    };
+    u8::try_from(note).unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -42,7 +185,7 @@ mod tests {
 
     #[test]
     fn we_can_get_correct_tuning_from_different_input_formats() {
-        let tuning = tuning("A, a, A#, a#, Ab, AB, ab, aB");
+        let tuning = tuning("A, a, A#, a#, Ab, AB, ab, aB").unwrap();
 
         assert_eq!(tuning.len(), 8);
         assert_eq!(format!("{}", tuning[0]), "A");
@@ -55,9 +198,26 @@ mod tests {
         assert_eq!(format!("{}", tuning[7]), "Ab");
     }
 
+    #[test]
+    fn we_can_spell_tunings_any_way() {
+        let tuning = tuning("Cb, E#, B#, Fb, F##, Bbb, G♯, H").unwrap();
+
+        let names: Vec<String> = tuning.iter().map(ToString::to_string).collect();
+        assert_eq!(names, ["Cb", "E#", "B#", "Fb", "F##", "Bbb", "G#", "B"]);
+    }
+
+    #[test]
+    fn we_can_place_enharmonic_octaves() {
+        let parsed: Tuning = "B#3, Cb4".parse().unwrap();
+        assert_eq!(parsed.strings[0].midi, 60);
+        assert_eq!(parsed.strings[1].midi, 59);
+        assert_eq!(format!("{}", parsed.strings[0]), "B#3");
+        assert_eq!(format!("{}", parsed.strings[1]), "Cb4");
+    }
+
     #[test]
     fn we_can_get_correct_tuning() {
-        let tuning = tuning("F#, B, G, E, Db");
+        let tuning = tuning("F#, B, G, E, Db").unwrap();
 
         assert_eq!(tuning.len(), 5);
         assert_eq!(format!("{}", tuning[0]), "F#");
@@ -67,9 +227,99 @@ mod tests {
         assert_eq!(format!("{}", tuning[4]), "Db");
     }
 
+    #[test]
+    fn we_can_place_e9_strings_in_their_octaves() {
+        let midi = midi_tuning(&tuning("F#, D#, G#, E, B, G#, F#, E, D, B").unwrap());
+
+        // F#4 D#4 G#4 E4 B3 G#3 F#3 E3 D3 B2
+        assert_eq!(midi, vec![66, 63, 68, 64, 59, 56, 54, 52, 50, 47]);
+    }
+
+    #[test]
+    fn we_can_place_lap_steel_strings_in_their_octaves() {
+        let midi = midi_tuning(&tuning("E, C#, A, F#, E, C#, A, F#").unwrap());
+
+        // E4 C#4 A3 F#3 E3 C#3 A2 F#2
+        assert_eq!(midi, vec![64, 61, 57, 54, 52, 49, 45, 42]);
+    }
+
     #[test]
     fn we_can_avoid_incorrect_turning_input() {
-        let tuning = tuning("Xb, BD, P Don Helms,");
-        assert_eq!(tuning.len(), 0);
+        assert_eq!(
+            tuning("Xb, BD, P Don Helms,"),
+            Err(TuningError::InvalidNote {
+                token: "Xb".to_string(),
+                index: 0,
+            })
+        );
+        assert_eq!(
+            tuning("E, B, P Don Helms"),
+            Err(TuningError::InvalidNote {
+                token: "P Don Helms".to_string(),
+                index: 2,
+            })
+        );
+        assert_eq!(
+            tuning("E, B,"),
+            Err(TuningError::InvalidNote {
+                token: String::new(),
+                index: 2,
+            })
+        );
+        assert_eq!(tuning(" "), Err(TuningError::Empty));
+    }
+
+    #[test]
+    fn we_can_parse_a_tuning_from_str() {
+        let parsed: Tuning = "E, C#, A".parse().unwrap();
+        assert_eq!(parsed.pitches(), tuning("E, C#, A").unwrap());
+        assert!("E, J".parse::<Tuning>().is_err());
+    }
+
+    #[test]
+    fn we_can_parse_octaves_and_midi_numbers() {
+        let parsed: Tuning = "G#4, E4, B3, 52, E".parse().unwrap();
+        let midi: Vec<u8> = parsed.strings.iter().map(|s| s.midi).collect();
+
+        // The last E follows the E3 before it
+        assert_eq!(midi, vec![68, 64, 59, 52, 52]);
+        assert_eq!(format!("{}", parsed.strings[0]), "G#4");
+        assert_eq!(format!("{}", parsed.strings[3]), "E3");
+        assert_eq!(
+            tuning("G#4, 52").unwrap(),
+            tuning("G#, E").unwrap(),
+            "octaves are dropped from plain note lists"
+        );
+    }
+
+    #[test]
+    fn we_can_keep_register_that_inference_would_miss() {
+        // A low B below a high F# is more than a tritone away
+        let parsed: Tuning = "F#4, B2".parse().unwrap();
+        assert_eq!(parsed.strings[1].midi, 47);
+        assert_eq!(parsed.strings[1].octave(), 2);
+    }
+
+    #[test]
+    fn we_can_reject_runs_of_accidentals() {
+        let notes = format!("E, B{}", "x".repeat(60));
+        assert_eq!(
+            tuning(&notes),
+            Err(TuningError::InvalidNote {
+                token: format!("B{}", "x".repeat(60)),
+                index: 1,
+            })
+        );
+        assert!("Cbbbbbbbbbbbbb4".parse::<Tuning>().is_err());
+    }
+
+    #[test]
+    fn we_can_reject_out_of_range_octaves() {
+        assert!("E10".parse::<Tuning>().is_err());
+        assert!("128".parse::<Tuning>().is_err());
+        assert!(matches!(
+            "E4, 4E".parse::<Tuning>(),
+            Err(TuningError::InvalidNote { index: 1, .. })
+        ));
     }
 }
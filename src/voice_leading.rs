@@ -0,0 +1,138 @@
+//! Voice leading — what moves when a pedal or lever is engaged.
+//!
+//! Pressing a pedal moves particular voices: on E9 the A and B pedals take the
+//! E major chord at a fret to the A major chord of the IV. This module compares
+//! the strings under the bar in two combinations of pedals and levers and
+//! reports which strings change, by how much, and the chords heard before and
+//! after.
+
+use crate::{
+    chords::{ChordMatch, IdentifyError, chords_from_pitches, sounding_pitches},
+    copedent::Copedent,
+    guitar::{Bar, Guitar},
+};
+
+/// One string before and after the change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoiceMove {
+    /// String number, counting from 1
+    pub string: usize,
+    /// MIDI note before the change
+    pub from: u8,
+    /// MIDI note after the change
+    pub to: u8,
+}
+
+impl VoiceMove {
+    /// How far the string moves, in semitones (up is positive)
+    pub fn semitones(&self) -> i16 {
+        i16::from(self.to) - i16::from(self.from)
+    }
+}
+
+/// The strings picked under the bar in two combinations and the chords they make
+#[derive(Debug, Clone)]
+pub struct VoiceLeading {
+    /// Every string picked, in the order given
+    pub voices: Vec<VoiceMove>,
+    /// The chords heard before the change
+    pub before: Vec<ChordMatch>,
+    /// The chords heard after the change
+    pub after: Vec<ChordMatch>,
+}
+
+impl VoiceLeading {
+    /// The strings that change pitch
+    pub fn moves(&self) -> impl Iterator<Item = &VoiceMove> {
+        self.voices.iter().filter(|voice| voice.from != voice.to)
+    }
+}
+
+/// Compare the strings picked under the bar when going from one combination of
+/// pedals and levers to another, e.g. from Open to A and B. Strings count
+/// from 1.
+pub fn voice_leading(
+    guitar: &Guitar,
+    copedent: &Copedent,
+    from: &[String],
+    to: &[String],
+    bar: Bar,
+    strings: &[usize],
+) -> Result<VoiceLeading, IdentifyError> {
+    let before = sounding_pitches(guitar, copedent, from, bar, strings)?;
+    let after = sounding_pitches(guitar, copedent, to, bar, strings)?;
+
+    Ok(VoiceLeading {
+        voices: strings
+            .iter()
+            .zip(before.iter().zip(&after))
+            .map(|(&string, (&from, &to))| VoiceMove { string, from, to })
+            .collect(),
+        before: chords_from_pitches(&before),
+        after: chords_from_pitches(&after),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{e9, names};
+
+    #[test]
+    fn test_pedals_down_move_i_to_iv() {
+        let guitar = e9();
+        let leading = voice_leading(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            &names(&["A", "B"]),
+            Bar::straight(0),
+            &[3, 4, 5, 6],
+        )
+        .unwrap();
+
+        // The B pedal raises the G#s a half step and the A pedal raises the B a whole step
+        let moves: Vec<(usize, i16)> = leading
+            .moves()
+            .map(|voice| (voice.string, voice.semitones()))
+            .collect();
+        assert_eq!(moves, [(3, 1), (5, 2), (6, 1)]);
+        assert_eq!(leading.voices.len(), 4);
+        assert_eq!(leading.before[0].name, "E Major Triad");
+        assert_eq!(leading.after[0].name, "A Major Triad");
+    }
+
+    #[test]
+    fn test_lever_lowers_strings() {
+        let guitar = e9();
+        let leading = voice_leading(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            &names(&["LKR"]),
+            Bar::straight(3),
+            &[4, 5, 6],
+        )
+        .unwrap();
+
+        // At fret 3 LKR lowers the G on string 4 to F#, taking G major to B minor
+        let moves: Vec<i16> = leading.moves().map(VoiceMove::semitones).collect();
+        assert_eq!(moves, [-1]);
+        assert_eq!(leading.before[0].name, "G Major Triad");
+        assert_eq!(leading.after[0].name, "B Minor Triad");
+    }
+
+    #[test]
+    fn test_voice_leading_rejects_unplayable() {
+        let guitar = e9();
+        let result = voice_leading(
+            &guitar,
+            &Copedent::default(),
+            &[],
+            &names(&["LKL", "LKR"]),
+            Bar::straight(0),
+            &[4],
+        );
+        assert!(matches!(result, Err(IdentifyError::Copedent(_))));
+    }
+}